@@ -0,0 +1,272 @@
+//! Blink patterns
+//!
+//! A `Blinker` plays blink patterns on LEDs connected to output pins of the device,
+//! e.g. to show status codes. Each pin has its own pattern, which can be:
+//! - Steady on or off.
+//! - A sequence of steps, each with an output level and a duration.
+//! - A Morse-like code made of short (`.`) and long (`-`) pulses,
+//!   e.g. `"...-"` for 3 short pulses followed by a long one.
+//!
+//! Sequences and codes can be repeated a number of times or forever.
+//!
+//! `tick()` must be called periodically with a timestamp. It advances all patterns and
+//! updates the outputs with at most one `set()`. The pins not used by the blinker keep
+//! their status.
+//!
+//! Time is provided by the caller as a free-running `u32` timestamp or tick count
+//! (e.g. milliseconds). Wrap-around is handled.
+//!
+//! ```no_run
+//! use linux_embedded_hal::I2cdev;
+//! use pcf857x::blink::{Blinker, Pattern, Repeat, Step};
+//! use pcf857x::{Pcf8574, PinFlag, SlaveAddr};
+//!
+//! const HEARTBEAT: [Step; 2] = [Step::on(100), Step::off(900)];
+//!
+//! let dev = I2cdev::new("/dev/i2c-1").unwrap();
+//! let expander = Pcf8574::new(dev, SlaveAddr::default());
+//! let mut leds = Blinker::new(expander, [PinFlag::P0, PinFlag::P1]);
+//! # let now = 0;
+//! leds.set_pattern(0, Pattern::Steps(&HEARTBEAT, Repeat::Forever), now).unwrap();
+//! let error_code = Pattern::Code {
+//!     code: "...-",
+//!     unit: 200,
+//!     pause: 2000,
+//!     repeat: Repeat::Times(3),
+//! };
+//! leds.set_pattern(1, error_code, now).unwrap();
+//! loop {
+//!     # let now = 0;
+//!     leds.tick(now).unwrap();
+//! }
+//! ```
+
+use crate::{Error, PinFlag, Port};
+
+/// Pattern step
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Step {
+    /// Output level during the step
+    pub on: bool,
+    /// Step duration
+    pub duration: u32,
+}
+
+impl Step {
+    /// Output on during `duration`
+    pub const fn on(duration: u32) -> Self {
+        Step { on: true, duration }
+    }
+
+    /// Output off during `duration`
+    pub const fn off(duration: u32) -> Self {
+        Step {
+            on: false,
+            duration,
+        }
+    }
+}
+
+/// Number of times a pattern is played
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Repeat {
+    /// Play the pattern this number of times, then switch the output off
+    Times(u16),
+    /// Play the pattern forever
+    Forever,
+}
+
+/// Blink pattern
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pattern<'a> {
+    /// Steady off
+    Off,
+    /// Steady on
+    On,
+    /// Sequence of steps
+    Steps(&'a [Step], Repeat),
+    /// Morse-like code
+    Code {
+        /// Pulses: `.` for short and `-` for long
+        code: &'a str,
+        /// Short pulse duration. Long pulses last 3 units and the pulses are
+        /// separated by 1 unit.
+        unit: u32,
+        /// Off time after the last pulse
+        pause: u32,
+        /// Number of times the code is played
+        repeat: Repeat,
+    },
+}
+
+impl<'a> Pattern<'a> {
+    /// Step at a position of the pattern, `None` at the end of the pattern.
+    fn step(&self, index: usize) -> Option<Step> {
+        match *self {
+            Pattern::Off | Pattern::On => None,
+            Pattern::Steps(steps, _) => steps.get(index).copied(),
+            Pattern::Code {
+                code, unit, pause, ..
+            } => {
+                let pulse = *code.as_bytes().get(index / 2)?;
+                let step = if index % 2 == 1 {
+                    let last = index / 2 + 1 == code.len();
+                    Step::off(if last { pause } else { unit })
+                } else if pulse == b'-' {
+                    Step::on(unit.saturating_mul(3))
+                } else {
+                    Step::on(unit)
+                };
+                Some(step)
+            }
+        }
+    }
+
+    fn repeat(&self) -> Repeat {
+        match *self {
+            Pattern::Off | Pattern::On => Repeat::Forever,
+            Pattern::Steps(_, repeat) | Pattern::Code { repeat, .. } => repeat,
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        match *self {
+            Pattern::Off | Pattern::On => true,
+            Pattern::Steps(steps, _) => steps.iter().any(|step| step.duration != 0),
+            Pattern::Code { code, unit, .. } => {
+                !code.is_empty() && unit != 0 && code.bytes().all(|c| c == b'.' || c == b'-')
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Channel<'a> {
+    pin: u16,
+    pattern: Pattern<'a>,
+    index: usize,
+    started_at: u32,
+    played: u16,
+    finished: bool,
+}
+
+impl<'a> Channel<'a> {
+    fn advance(&mut self, now: u32) {
+        if self.finished {
+            return;
+        }
+        while let Some(step) = self.pattern.step(self.index) {
+            if now.wrapping_sub(self.started_at) < step.duration {
+                return;
+            }
+            self.started_at = self.started_at.wrapping_add(step.duration);
+            self.index += 1;
+            if self.pattern.step(self.index).is_none() {
+                self.index = 0;
+                self.played = self.played.saturating_add(1);
+                if let Repeat::Times(times) = self.pattern.repeat() {
+                    if self.played >= times {
+                        self.finished = true;
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    fn is_on(&self) -> bool {
+        match self.pattern {
+            Pattern::Off => false,
+            Pattern::On => true,
+            _ if self.finished => false,
+            pattern => pattern.step(self.index).is_some_and(|step| step.on),
+        }
+    }
+}
+
+/// Blink pattern player for `N` output pins
+#[derive(Debug)]
+pub struct Blinker<'a, P, const N: usize> {
+    port: P,
+    channels: [Channel<'a>; N],
+}
+
+impl<'a, P: Port, const N: usize> Blinker<'a, P, N> {
+    /// Create a new blinker with the pin of each channel. All channels start off.
+    /// No I²C communication is done.
+    pub fn new(port: P, pins: [PinFlag; N]) -> Self {
+        Blinker {
+            port,
+            channels: pins.map(|pin| Channel {
+                pin: pin.mask,
+                pattern: Pattern::Off,
+                index: 0,
+                started_at: 0,
+                played: 0,
+                finished: false,
+            }),
+        }
+    }
+
+    /// Destroy the blinker instance, return the device.
+    pub fn destroy(self) -> P {
+        self.port
+    }
+
+    /// Start playing a pattern on a channel from the beginning.
+    /// The output is updated with the next `tick()`.
+    ///
+    /// Returns `Error::InvalidInputData` if the channel does not exist or the
+    /// pattern is invalid (e.g. a sequence where all steps last 0 or a code with
+    /// characters other than `.` and `-`).
+    pub fn set_pattern(
+        &mut self,
+        channel: usize,
+        pattern: Pattern<'a>,
+        now: u32,
+    ) -> Result<(), Error<P::BusError>> {
+        let channel = self
+            .channels
+            .get_mut(channel)
+            .ok_or(Error::InvalidInputData)?;
+        if !pattern.is_valid() {
+            return Err(Error::InvalidInputData);
+        }
+        channel.pattern = pattern;
+        channel.index = 0;
+        channel.started_at = now;
+        channel.played = 0;
+        channel.finished = pattern.repeat() == Repeat::Times(0);
+        Ok(())
+    }
+
+    /// Whether the pattern of a channel has been played the requested number
+    /// of times. Patterns repeated forever never finish.
+    pub fn is_finished(&self, channel: usize) -> Result<bool, Error<P::BusError>> {
+        self.channels
+            .get(channel)
+            .map(|channel| channel.finished)
+            .ok_or(Error::InvalidInputData)
+    }
+
+    /// Advance all patterns and update the outputs.
+    ///
+    /// This does a single `set()` if any output changes.
+    pub fn tick(&mut self, now: u32) -> Result<(), Error<P::BusError>> {
+        let mut mask = 0;
+        let mut on = 0;
+        for channel in &mut self.channels {
+            channel.advance(now);
+            mask |= channel.pin;
+            if channel.is_on() {
+                on |= channel.pin;
+            }
+        }
+        let last_set = self.port.last_set();
+        let word = (last_set & !mask) | on;
+        if word != last_set {
+            self.port.set(word)?;
+        }
+        Ok(())
+    }
+}
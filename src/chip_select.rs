@@ -0,0 +1,172 @@
+//! Chip selects on the device for a shared SPI bus
+//!
+//! When there are more SPI peripherals than free microcontroller pins, their chip select
+//! lines can be connected to pins of the device. A `SharedBus` wraps an `SpiBus` (e.g. the
+//! hardware SPI peripheral of the microcontroller) and hands out `SharedDevice`s, each
+//! using one pin of the split device as active-low chip select. `SharedDevice` implements
+//! the `SpiDevice` trait from `embedded-hal`.
+//!
+//! The bus is borrowed for the whole transaction and the chip select is only asserted
+//! during a transaction, so at most one chip select is active at a time. Asserting and
+//! deasserting a chip select is done with a single write each, keeping the status
+//! of the other pins. If a chip select cannot be deasserted, the transactions of the
+//! other devices return `ChipSelectError::Busy` until a later transaction of its device
+//! deasserts it.
+//!
+//! ```no_run
+//! use embedded_hal::spi::{Operation, SpiDevice};
+//! use linux_embedded_hal::{Delay, I2cdev, SpidevBus};
+//! use pcf857x::{chip_select::SharedBus, Pcf8574, SlaveAddr};
+//!
+//! let dev = I2cdev::new("/dev/i2c-1").unwrap();
+//! let expander = Pcf8574::new(dev, SlaveAddr::default());
+//! let parts = expander.split();
+//! let bus = SharedBus::new(SpidevBus::open("/dev/spidev0.0").unwrap());
+//! let mut eeprom = bus.device(parts.p0, Delay).unwrap();
+//! let mut adc = bus.device(parts.p1, Delay).unwrap();
+//! let mut data = [0; 4];
+//! eeprom
+//!     .transaction(&mut [Operation::Write(&[0x03, 0x00]), Operation::Read(&mut data)])
+//!     .unwrap();
+//! adc.transfer_in_place(&mut data).unwrap();
+//! ```
+
+use core::cell::{Cell, RefCell};
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::OutputPin;
+use embedded_hal::spi::{ErrorKind, ErrorType, Operation, SpiBus, SpiDevice};
+
+/// Shared bus device errors
+#[derive(Debug)]
+pub enum ChipSelectError<B, C> {
+    /// SPI bus error
+    Bus(B),
+    /// Chip select pin error
+    ChipSelect(C),
+    /// The bus is in use by another transaction or the chip select of another
+    /// device could not be deasserted
+    Busy,
+}
+
+impl<B, C> embedded_hal::spi::Error for ChipSelectError<B, C>
+where
+    B: embedded_hal::spi::Error,
+    C: core::fmt::Debug,
+{
+    fn kind(&self) -> ErrorKind {
+        match self {
+            ChipSelectError::Bus(error) => error.kind(),
+            ChipSelectError::ChipSelect(_) => ErrorKind::ChipSelectFault,
+            ChipSelectError::Busy => ErrorKind::Other,
+        }
+    }
+}
+
+/// SPI bus shared by devices with chip selects on the device
+#[derive(Debug)]
+pub struct SharedBus<BUS> {
+    bus: RefCell<BUS>,
+    cs_stuck: Cell<bool>,
+}
+
+impl<BUS: SpiBus> SharedBus<BUS> {
+    /// Create a new shared bus.
+    pub fn new(bus: BUS) -> Self {
+        SharedBus {
+            bus: RefCell::new(bus),
+            cs_stuck: Cell::new(false),
+        }
+    }
+
+    /// Destroy the shared bus, return the SPI bus.
+    pub fn destroy(self) -> BUS {
+        self.bus.into_inner()
+    }
+
+    /// Create a device using a pin (usually of the split device) as active-low
+    /// chip select. The chip select is deasserted.
+    pub fn device<CS, D>(
+        &self,
+        mut cs: CS,
+        delay: D,
+    ) -> Result<SharedDevice<'_, BUS, CS, D>, CS::Error>
+    where
+        CS: OutputPin,
+        D: DelayNs,
+    {
+        cs.set_high()?;
+        Ok(SharedDevice {
+            bus: &self.bus,
+            bus_cs_stuck: &self.cs_stuck,
+            cs,
+            cs_stuck: false,
+            delay,
+        })
+    }
+}
+
+/// SPI device on a shared bus
+#[derive(Debug)]
+pub struct SharedDevice<'a, BUS, CS, D> {
+    bus: &'a RefCell<BUS>,
+    /// Whether the chip select of any device could not be deasserted
+    bus_cs_stuck: &'a Cell<bool>,
+    cs: CS,
+    /// Whether the chip select of this device could not be deasserted
+    cs_stuck: bool,
+    delay: D,
+}
+
+impl<'a, BUS, CS, D> SharedDevice<'a, BUS, CS, D> {
+    /// Destroy the device, return the chip select pin and delay.
+    pub fn destroy(self) -> (CS, D) {
+        (self.cs, self.delay)
+    }
+}
+
+impl<'a, BUS, CS, D> ErrorType for SharedDevice<'a, BUS, CS, D>
+where
+    BUS: SpiBus,
+    CS: OutputPin,
+{
+    type Error = ChipSelectError<BUS::Error, CS::Error>;
+}
+
+impl<'a, BUS, CS, D> SpiDevice for SharedDevice<'a, BUS, CS, D>
+where
+    BUS: SpiBus,
+    CS: OutputPin,
+    D: DelayNs,
+{
+    fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), Self::Error> {
+        // another chip select may still be asserted
+        if self.bus_cs_stuck.get() && !self.cs_stuck {
+            return Err(ChipSelectError::Busy);
+        }
+        let mut bus = self
+            .bus
+            .try_borrow_mut()
+            .map_err(|_| ChipSelectError::Busy)?;
+        self.cs.set_low().map_err(ChipSelectError::ChipSelect)?;
+        let result = operations
+            .iter_mut()
+            .try_for_each(|operation| match operation {
+                Operation::Read(words) => bus.read(words),
+                Operation::Write(words) => bus.write(words),
+                Operation::Transfer(read, write) => bus.transfer(read, write),
+                Operation::TransferInPlace(words) => bus.transfer_in_place(words),
+                Operation::DelayNs(ns) => {
+                    bus.flush()?;
+                    self.delay.delay_ns(*ns);
+                    Ok(())
+                }
+            })
+            .and_then(|_| bus.flush());
+        // deassert even if the transfer failed, the bus error is returned first
+        let deselect = self.cs.set_high();
+        self.cs_stuck = deselect.is_err();
+        self.bus_cs_stuck.set(self.cs_stuck);
+        result.map_err(ChipSelectError::Bus)?;
+        deselect.map_err(ChipSelectError::ChipSelect)
+    }
+}
@@ -0,0 +1,194 @@
+//! DIP switch, BCD thumbwheel and Gray code readers
+//!
+//! Typed readers for inputs that encode a number in several pins:
+//! - `DipSwitch`: plain binary value, e.g. a configuration DIP switch.
+//! - `BcdThumbwheel`: decimal digits in BCD, e.g. thumbwheel switches.
+//!   Digits above 9 are rejected with `Error::InvalidInputData`.
+//! - `GrayCode`: absolute position in Gray code, e.g. absolute encoders.
+//!
+//! Each reader takes a pin for each bit, so any pins of the device can be used in any
+//! order, including P10-P17 on a PCF8575. Each read does a single `get()`.
+//! A reader with more than 16 bits does not compile.
+//!
+//! Inputs wired active-low (to ground, using the weak pull-up of the device) can be
+//! inverted with the reader inversion mask. See `set_inversion_mask()`.
+//!
+//! ```no_run
+//! use linux_embedded_hal::I2cdev;
+//! use pcf857x::code_reader::{BcdThumbwheel, DipSwitch};
+//! use pcf857x::{Pcf8575, PinFlag, SlaveAddr};
+//!
+//! let dev = I2cdev::new("/dev/i2c-1").unwrap();
+//! let mut expander = Pcf8575::new(dev, SlaveAddr::default());
+//! let mut address = DipSwitch::new(
+//!     &mut expander,
+//!     [PinFlag::P0, PinFlag::P1, PinFlag::P2, PinFlag::P3],
+//! );
+//! address.set_inversion_mask(0x000F);
+//! let address = address.read().unwrap();
+//!
+//! let mut setpoint = BcdThumbwheel::new(
+//!     &mut expander,
+//!     [
+//!         [PinFlag::P10, PinFlag::P11, PinFlag::P12, PinFlag::P13],
+//!         [PinFlag::P14, PinFlag::P15, PinFlag::P16, PinFlag::P17],
+//!     ],
+//! );
+//! let setpoint = setpoint.read().unwrap();
+//! ```
+
+use crate::{Error, PinFlag, Port};
+
+/// Read the pins with a single `get()` and put the level of each pin into the
+/// bit of the same position in the returned value.
+fn read_bits<P: Port>(
+    port: &mut P,
+    pins: &[PinFlag],
+    inversion_mask: u16,
+) -> Result<u16, Error<P::BusError>> {
+    let mask = pins.iter().fold(0, |mask, pin| mask | pin.mask);
+    let levels = port.get(PinFlag { mask })? ^ inversion_mask;
+    let value = pins
+        .iter()
+        .enumerate()
+        .filter(|(_, pin)| levels & pin.mask != 0)
+        .fold(0, |value, (i, _)| value | (1 << i));
+    Ok(value)
+}
+
+/// DIP switch with `N` positions
+#[derive(Debug)]
+pub struct DipSwitch<P, const N: usize> {
+    port: P,
+    pins: [PinFlag; N],
+    inversion_mask: u16,
+}
+
+impl<P: Port, const N: usize> DipSwitch<P, N> {
+    /// Create a new DIP switch reader. `pins[0]` is the least significant bit.
+    pub fn new(port: P, pins: [PinFlag; N]) -> Self {
+        const { assert!(N <= 16, "a DIP switch has at most 16 positions") };
+        DipSwitch {
+            port,
+            pins,
+            inversion_mask: 0,
+        }
+    }
+
+    /// Destroy the reader, return the device.
+    pub fn destroy(self) -> P {
+        self.port
+    }
+
+    /// Set the mask of device pins whose level is inverted before decoding.
+    pub fn set_inversion_mask(&mut self, mask: u16) {
+        self.inversion_mask = mask;
+    }
+
+    /// Read the switch value.
+    pub fn read(&mut self) -> Result<u16, Error<P::BusError>> {
+        read_bits(&mut self.port, &self.pins, self.inversion_mask)
+    }
+}
+
+/// BCD thumbwheel switches with `D` decimal digits (up to 4)
+#[derive(Debug)]
+pub struct BcdThumbwheel<P, const D: usize> {
+    port: P,
+    pins: [[PinFlag; 4]; D],
+    inversion_mask: u16,
+}
+
+impl<P: Port, const D: usize> BcdThumbwheel<P, D> {
+    /// Create a new BCD thumbwheel reader.
+    ///
+    /// The digits are given most significant first, each one with the pins of
+    /// its bits 1, 2, 4 and 8. More than 4 digits do not compile:
+    ///
+    /// ```compile_fail
+    /// # use pcf857x::{code_reader::BcdThumbwheel, Pcf8575, PinFlag, SlaveAddr};
+    /// # let dev = linux_embedded_hal::I2cdev::new("/dev/i2c-1").unwrap();
+    /// let expander = Pcf8575::new(dev, SlaveAddr::default());
+    /// let digit = [PinFlag::P0, PinFlag::P1, PinFlag::P2, PinFlag::P3];
+    /// let thumbwheel = BcdThumbwheel::new(expander, [digit; 5]);
+    /// ```
+    pub fn new(port: P, pins: [[PinFlag; 4]; D]) -> Self {
+        const { assert!(D <= 4, "a BCD thumbwheel has at most 4 digits") };
+        BcdThumbwheel {
+            port,
+            pins,
+            inversion_mask: 0,
+        }
+    }
+
+    /// Destroy the reader, return the device.
+    pub fn destroy(self) -> P {
+        self.port
+    }
+
+    /// Set the mask of device pins whose level is inverted before decoding.
+    pub fn set_inversion_mask(&mut self, mask: u16) {
+        self.inversion_mask = mask;
+    }
+
+    /// Read the decimal value.
+    ///
+    /// Returns `Error::InvalidInputData` if any digit is above 9.
+    pub fn read(&mut self) -> Result<u32, Error<P::BusError>> {
+        let mut pins = [PinFlag { mask: 0 }; 16];
+        let pins = &mut pins[..4 * D];
+        for (pin, digit_pin) in pins.iter_mut().zip(self.pins.iter().flatten()) {
+            *pin = *digit_pin;
+        }
+        let bits = read_bits(&mut self.port, pins, self.inversion_mask)?;
+        (0..D).try_fold(0, |value, i| {
+            let digit = (bits >> (4 * i)) & 0xF;
+            if digit > 9 {
+                return Err(Error::InvalidInputData);
+            }
+            Ok(value * 10 + u32::from(digit))
+        })
+    }
+}
+
+/// Gray code input with `N` bits
+#[derive(Debug)]
+pub struct GrayCode<P, const N: usize> {
+    port: P,
+    pins: [PinFlag; N],
+    inversion_mask: u16,
+}
+
+impl<P: Port, const N: usize> GrayCode<P, N> {
+    /// Create a new Gray code reader. `pins[0]` is the least significant bit.
+    pub fn new(port: P, pins: [PinFlag; N]) -> Self {
+        const { assert!(N <= 16, "a Gray code input has at most 16 bits") };
+        GrayCode {
+            port,
+            pins,
+            inversion_mask: 0,
+        }
+    }
+
+    /// Destroy the reader, return the device.
+    pub fn destroy(self) -> P {
+        self.port
+    }
+
+    /// Set the mask of device pins whose level is inverted before decoding.
+    pub fn set_inversion_mask(&mut self, mask: u16) {
+        self.inversion_mask = mask;
+    }
+
+    /// Read the position, converted to binary.
+    pub fn read(&mut self) -> Result<u16, Error<P::BusError>> {
+        let gray = read_bits(&mut self.port, &self.pins, self.inversion_mask)?;
+        let mut value = gray;
+        let mut shift = gray >> 1;
+        while shift != 0 {
+            value ^= shift;
+            shift >>= 1;
+        }
+        Ok(value)
+    }
+}
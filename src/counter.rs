@@ -0,0 +1,191 @@
+//! Pulse counting and frequency measurement
+//!
+//! A `PulseCounter` counts edges on input pins from port snapshots, e.g. pulses of
+//! flow meters or tachometers. The snapshots can come from periodic `get()` calls or
+//! reads triggered by the INT output of the device. Every pulse must be seen in at
+//! least one snapshot, so this is only suitable for low pulse rates.
+//!
+//! Each pin counts rising edges, falling edges or both. The counters are `u32` and
+//! wrap around on overflow, so the number of edges between two readings is always
+//! `current.wrapping_sub(previous)`.
+//!
+//! The time between the last two counted edges of the same type of each pin gives its
+//! period and frequency. When counting both edges, the period is measured between two
+//! rising or two falling edges, so it is a full cycle whatever the duty cycle. Time is
+//! provided by the caller as a free-running `u32` timestamp or tick count
+//! (e.g. milliseconds). Wrap-around is handled. The timing resolution is given by how
+//! often the port is read.
+//!
+//! ```no_run
+//! use linux_embedded_hal::I2cdev;
+//! use pcf857x::counter::{Edge, PulseCounter};
+//! use pcf857x::{Pcf8574, PinFlag, SlaveAddr};
+//!
+//! let dev = I2cdev::new("/dev/i2c-1").unwrap();
+//! let mut expander = Pcf8574::new(dev, SlaveAddr::default());
+//! let mut counter = PulseCounter::new(PinFlag::P0 | PinFlag::P1, Edge::Rising);
+//! loop {
+//!     // wait for INT to go low or for the next polling period
+//!     # let now = 0;
+//!     counter.poll(&mut expander, now).unwrap();
+//!     let flow_pulses = counter.count(PinFlag::P0);
+//!     let rpm = counter.frequency(PinFlag::P1, now, 1000).map(|hz| hz * 60.0);
+//! }
+//! ```
+
+use crate::port::{pins, Port};
+use crate::{Error, PinFlag};
+
+/// Edges to count
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edge {
+    /// Low to high transitions
+    Rising,
+    /// High to low transitions
+    Falling,
+    /// Both transitions. Each cycle counts twice.
+    Both,
+}
+
+/// Edge counter for a group of input pins
+#[derive(Debug)]
+pub struct PulseCounter {
+    mask: u16,
+    rising: u16,
+    falling: u16,
+    initialized: bool,
+    levels: u16,
+    counts: [u32; 16],
+    rising_at: [u32; 16],
+    falling_at: [u32; 16],
+    periods: [Option<u32>; 16],
+    seen_rising: u16,
+    seen_falling: u16,
+}
+
+impl PulseCounter {
+    /// Create a new counter for the selected input pins, counting the same edges
+    /// on all of them.
+    pub fn new(mask: PinFlag, edge: Edge) -> Self {
+        let mut counter = PulseCounter {
+            mask: mask.mask,
+            rising: 0,
+            falling: 0,
+            initialized: false,
+            levels: 0,
+            counts: [0; 16],
+            rising_at: [0; 16],
+            falling_at: [0; 16],
+            periods: [None; 16],
+            seen_rising: 0,
+            seen_falling: 0,
+        };
+        counter.set_edge(mask, edge);
+        counter
+    }
+
+    /// Set the edges counted on the selected pins.
+    pub fn set_edge(&mut self, mask: PinFlag, edge: Edge) {
+        let mask = mask.mask & self.mask;
+        let (rising, falling) = match edge {
+            Edge::Rising => (true, false),
+            Edge::Falling => (false, true),
+            Edge::Both => (true, true),
+        };
+        self.rising = if rising {
+            self.rising | mask
+        } else {
+            self.rising & !mask
+        };
+        self.falling = if falling {
+            self.falling | mask
+        } else {
+            self.falling & !mask
+        };
+    }
+
+    /// Read the selected pins of a device with a single `get()` and process the snapshot.
+    pub fn poll<P: Port>(&mut self, port: &mut P, now: u32) -> Result<(), Error<P::BusError>> {
+        let snapshot = port.get(PinFlag { mask: self.mask })?;
+        self.update(snapshot, now);
+        Ok(())
+    }
+
+    /// Process a snapshot of the port.
+    ///
+    /// The first snapshot is taken as the initial state and counts no edges.
+    pub fn update(&mut self, snapshot: u16, now: u32) {
+        let levels = snapshot & self.mask;
+        if !self.initialized {
+            self.initialized = true;
+            self.levels = levels;
+            return;
+        }
+        let rising = levels & !self.levels & self.rising;
+        let falling = !levels & self.levels & self.falling;
+        self.levels = levels;
+        for pin in pins(rising | falling) {
+            self.counts[pin] = self.counts[pin].wrapping_add(1);
+            let (seen, edge_at) = if rising & (1 << pin) != 0 {
+                (&mut self.seen_rising, &mut self.rising_at)
+            } else {
+                (&mut self.seen_falling, &mut self.falling_at)
+            };
+            if *seen & (1 << pin) != 0 {
+                self.periods[pin] = Some(now.wrapping_sub(edge_at[pin]));
+            }
+            *seen |= 1 << pin;
+            edge_at[pin] = now;
+        }
+    }
+
+    /// Number of edges counted on a pin. Wraps around on overflow.
+    ///
+    /// If several pins are selected, the lowest one is used.
+    pub fn count(&self, pin: PinFlag) -> u32 {
+        index(pin).map_or(0, |i| self.counts[i])
+    }
+
+    /// Reset the count and the period measurement of the selected pins.
+    pub fn reset(&mut self, mask: PinFlag) {
+        for pin in pins(mask.mask) {
+            self.counts[pin] = 0;
+            self.periods[pin] = None;
+        }
+        self.seen_rising &= !mask.mask;
+        self.seen_falling &= !mask.mask;
+    }
+
+    /// Period of a pin: the time between its last two counted edges of the same type,
+    /// in the same units as the timestamps. If the time since the edge starting the
+    /// next period is already longer, that is returned instead, so that the period
+    /// grows as the pulses slow down or stop.
+    ///
+    /// Returns `None` until two edges of the same type have been counted.
+    /// If several pins are selected, the lowest one is used.
+    pub fn period(&self, pin: PinFlag, now: u32) -> Option<u32> {
+        let i = index(pin)?;
+        let since = |seen: u16, edge_at: &[u32; 16]| {
+            if seen & (1 << i) != 0 {
+                now.wrapping_sub(edge_at[i])
+            } else {
+                0
+            }
+        };
+        let since_last = since(self.seen_rising, &self.rising_at)
+            .max(since(self.seen_falling, &self.falling_at));
+        self.periods[i].map(|period| period.max(since_last))
+    }
+
+    /// Frequency of a pin in Hz, given the number of timestamp ticks per second.
+    /// See `period()`.
+    pub fn frequency(&self, pin: PinFlag, now: u32, ticks_per_second: u32) -> Option<f32> {
+        self.period(pin, now)
+            .filter(|period| *period != 0)
+            .map(|period| ticks_per_second as f32 / period as f32)
+    }
+}
+
+fn index(pin: PinFlag) -> Option<usize> {
+    pins(pin.mask).next()
+}
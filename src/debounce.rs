@@ -0,0 +1,136 @@
+//! Button debouncing
+//!
+//! A `Debouncer` wraps a device (or a mutable reference to it) and a mask of input pins.
+//! Each call to `poll()` reads all the selected pins with a single `get()` and updates
+//! the debounced state of every pin. A pin only changes its debounced state after its
+//! input level has been stable for the configured debounce time.
+//!
+//! Time is provided by the caller as a free-running `u32` timestamp or tick count
+//! (e.g. milliseconds). Wrap-around is handled.
+//!
+//! Pressed means the pin level went high. Buttons wired active-low (to ground, using the
+//! weak pull-up of the device) can be handled by setting the device inversion mask.
+//! See `set_inversion_mask()`.
+//!
+//! ```no_run
+//! use linux_embedded_hal::I2cdev;
+//! use pcf857x::{debounce::Debouncer, Pcf8574, PinFlag, SlaveAddr};
+//!
+//! let dev = I2cdev::new("/dev/i2c-1").unwrap();
+//! let mut expander = Pcf8574::new(dev, SlaveAddr::default());
+//! expander.set_inversion_mask(0b0000_0011);
+//! let mut buttons = Debouncer::new(&mut expander, PinFlag::P0 | PinFlag::P1, 20);
+//! # let now = 0;
+//! let events = buttons.poll(now).unwrap();
+//! if events.was_pressed(PinFlag::P0) {
+//!     // ...
+//! }
+//! ```
+
+use crate::port::{pins, Port};
+use crate::{Error, PinFlag};
+
+/// Debounced state changes detected during a poll
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Events {
+    /// Mask of the pins whose debounced state went high
+    pub pressed: u16,
+    /// Mask of the pins whose debounced state went low
+    pub released: u16,
+}
+
+impl Events {
+    /// Whether no state changed
+    pub fn is_empty(&self) -> bool {
+        self.pressed == 0 && self.released == 0
+    }
+
+    /// Whether any of the selected pins was pressed
+    pub fn was_pressed(&self, pin: PinFlag) -> bool {
+        self.pressed & pin.mask != 0
+    }
+
+    /// Whether any of the selected pins was released
+    pub fn was_released(&self, pin: PinFlag) -> bool {
+        self.released & pin.mask != 0
+    }
+}
+
+/// Debouncer for a group of input pins of a device
+#[derive(Debug)]
+pub struct Debouncer<P> {
+    port: P,
+    mask: u16,
+    debounce_time: u32,
+    initialized: bool,
+    raw: u16,
+    state: u16,
+    changed_at: [u32; 16],
+}
+
+impl<P: Port> Debouncer<P> {
+    /// Create a new debouncer for the selected input pins.
+    ///
+    /// `debounce_time` is the time a pin level must be stable before it is accepted,
+    /// in the same units as the timestamps passed to `poll()`.
+    pub fn new(port: P, mask: PinFlag, debounce_time: u32) -> Self {
+        Debouncer {
+            port,
+            mask: mask.mask,
+            debounce_time,
+            initialized: false,
+            raw: 0,
+            state: 0,
+            changed_at: [0; 16],
+        }
+    }
+
+    /// Destroy the debouncer, return the device.
+    pub fn destroy(self) -> P {
+        self.port
+    }
+
+    /// Read the input pins and update the debounced state.
+    ///
+    /// The first poll takes the read levels as the initial debounced state and
+    /// reports no events.
+    pub fn poll(&mut self, now: u32) -> Result<Events, Error<P::BusError>> {
+        let raw = self.port.get(PinFlag { mask: self.mask })?;
+        Ok(self.update(raw, now))
+    }
+
+    /// Debounced state of the selected pins
+    pub fn state(&self) -> u16 {
+        self.state
+    }
+
+    /// Whether the debounced state of any of the selected pins is high
+    pub fn is_high(&self, pin: PinFlag) -> bool {
+        self.state & pin.mask != 0
+    }
+
+    fn update(&mut self, raw: u16, now: u32) -> Events {
+        let raw = raw & self.mask;
+        if !self.initialized {
+            self.initialized = true;
+            self.raw = raw;
+            self.state = raw;
+            self.changed_at = [now; 16];
+            return Events::default();
+        }
+        for pin in pins(raw ^ self.raw) {
+            self.changed_at[pin] = now;
+        }
+        self.raw = raw;
+        let stable = pins(self.mask)
+            .filter(|&pin| now.wrapping_sub(self.changed_at[pin]) >= self.debounce_time)
+            .fold(0, |stable, pin| stable | (1 << pin));
+        let state = (self.state & !stable) | (raw & stable);
+        let events = Events {
+            pressed: state & !self.state,
+            released: self.state & !state,
+        };
+        self.state = state;
+        events
+    }
+}
@@ -1,5 +1,5 @@
 use core::cell;
-use embedded_hal::i2c::I2c;
+use embedded_hal::{delay::DelayNs, i2c::I2c};
 
 use crate::split_pins::pcf8574;
 use crate::{Error, PinFlag, SlaveAddr};
@@ -21,6 +21,10 @@ macro_rules! pcf8574 {
             pub(crate) address: u8,
             /// Last status set to output pins, used to conserve its status while doing a read.
             pub(crate) last_set_mask: u8,
+            /// Pins whose logical level is inverted with respect to the physical one.
+            pub(crate) inversion_mask: u8,
+            /// Output pins read back after each write.
+            pub(crate) verify_mask: u8,
         }
 
         impl<I2C, E> $device_name<I2C>
@@ -33,6 +37,8 @@ macro_rules! pcf8574 {
                     i2c,
                     address: address.addr($default_address),
                     last_set_mask: 0,
+                    inversion_mask: 0,
+                    verify_mask: 0,
                 };
                 $device_name {
                     data: cell::RefCell::new(data),
@@ -55,9 +61,43 @@ macro_rules! pcf8574 {
                 f(dev)
             }
 
+            /// Set the polarity inversion mask.
+            ///
+            /// Pins whose bit is set in the mask are handled in inverted (active-low) logic:
+            /// `set()`, `get()`, `write_array()`, `read_array()` and the individual pins
+            /// work with logical levels, which are inverted with respect to the physical
+            /// level of these pins. No I²C communication is done.
+            pub fn set_inversion_mask(&mut self, mask: u8) {
+                self.data.get_mut().inversion_mask = mask;
+            }
+
+            /// Set the output pins to be verified, or `None` to disable the verification.
+            ///
+            /// After each write done by `set()`, the individual pins and the modules using
+            /// the device, the port is read back. If any of the selected pins set high reads
+            /// back low (e.g. due to a shorted driver transistor), the write returns
+            /// `Error::OutputMismatch` with the mask of these pins.
+            /// Only output pins can be verified: inputs may be held low externally while set
+            /// high (e.g. a power-good signal while the rail comes up) and must not be selected.
+            /// Pins set low cannot be verified since they are actively driven low.
+            /// No I²C communication is done.
+            /// The mask of the pins can be created with a combination of
+            /// `PinFlag::P0` to `PinFlag::P7`.
+            pub fn set_verify(&mut self, mask: Option<PinFlag>) -> Result<(), Error<E>> {
+                let mask = mask.map_or(0, |mask| mask.mask);
+                if (mask >> 8) != 0 {
+                    return Err(Error::InvalidInputData);
+                }
+                self.data.get_mut().verify_mask = mask as u8;
+                Ok(())
+            }
+
             /// Set the status of all I/O pins.
             pub fn set(&mut self, bits: u8) -> Result<(), Error<E>> {
-                self.do_on_acquired(|dev| Self::_set(dev, bits))
+                self.do_on_acquired(|dev| {
+                    let bits = bits ^ dev.inversion_mask;
+                    Self::_set(dev, bits)
+                })
             }
 
             pub(crate) fn _set(
@@ -67,22 +107,88 @@ macro_rules! pcf8574 {
                 let address = dev.address;
                 dev.i2c.write(address, &[bits]).map_err(Error::I2C)?;
                 dev.last_set_mask = bits;
+                if dev.verify_mask != 0 {
+                    let mut read = [0];
+                    dev.i2c.read(address, &mut read).map_err(Error::I2C)?;
+                    let mismatch = bits & dev.verify_mask & !read[0];
+                    if mismatch != 0 {
+                        return Err(Error::OutputMismatch(mismatch.into()));
+                    }
+                }
                 Ok(())
             }
 
             /// Set the status of all I/O pins repeatedly by looping through each array element
+            ///
+            /// If an inversion mask is set, the data is sent in chunks of up to
+            /// 32 elements, one I²C write per chunk.
             pub fn write_array(&mut self, data: &[u8]) -> Result<(), Error<E>> {
                 if let Some(last) = data.last() {
                     self.do_on_acquired(|mut dev| {
                         let address = dev.address;
-                        dev.i2c.write(address, &data).map_err(Error::I2C)?;
-                        dev.last_set_mask = *last;
+                        let inversion_mask = dev.inversion_mask;
+                        if inversion_mask == 0 {
+                            dev.i2c.write(address, &data).map_err(Error::I2C)?;
+                        } else {
+                            let mut buffer = [0; 32];
+                            for chunk in data.chunks(buffer.len()) {
+                                for (physical, logical) in buffer.iter_mut().zip(chunk) {
+                                    *physical = logical ^ inversion_mask;
+                                }
+                                dev.i2c
+                                    .write(address, &buffer[..chunk.len()])
+                                    .map_err(Error::I2C)?;
+                            }
+                        }
+                        dev.last_set_mask = *last ^ inversion_mask;
                         Ok(())
                     })?;
                 }
                 Ok(())
             }
 
+            /// Drive the selected pins to a level for at least `duration_ns` nanoseconds,
+            /// then restore their previous status (e.g. to pulse a reset line).
+            ///
+            /// The other pins keep their status.
+            /// The mask of the pins can be created with a combination of
+            /// `PinFlag::P0` to `PinFlag::P7`.
+            pub fn pulse<D: DelayNs>(
+                &mut self,
+                mask: PinFlag,
+                high: bool,
+                duration_ns: u32,
+                delay: &mut D,
+            ) -> Result<(), Error<E>> {
+                if (mask.mask >> 8) != 0 {
+                    return Err(Error::InvalidInputData);
+                }
+                self._pulse(mask, high, duration_ns, delay)
+            }
+
+            pub(crate) fn _pulse<D: DelayNs>(
+                &self,
+                mask: PinFlag,
+                high: bool,
+                duration_ns: u32,
+                delay: &mut D,
+            ) -> Result<(), Error<E>> {
+                let mask = mask.mask as u8;
+                let previous = self.do_on_acquired(|dev| Ok(dev.last_set_mask))?;
+                let pulse = self.do_on_acquired(|dev| {
+                    let level = if high { mask } else { 0 };
+                    let bits = (previous & !mask) | ((level ^ dev.inversion_mask) & mask);
+                    Self::_set(dev, bits)
+                });
+                delay.delay_ns(duration_ns);
+                // restore even if the pulse failed, the first error is returned
+                let restore = self.do_on_acquired(|dev| {
+                    let bits = (dev.last_set_mask & !mask) | (previous & mask);
+                    Self::_set(dev, bits)
+                });
+                pulse.and(restore)
+            }
+
             /// Split device into individual pins
             pub fn split(&self) -> pcf8574::Parts<'_, $device_name<I2C>, E> {
                 pcf8574::Parts::new(&self)
@@ -116,7 +222,38 @@ macro_rules! pcf8574 {
                 dev.i2c
                     .read(address, &mut bits)
                     .map_err(Error::I2C)
-                    .and(Ok(bits[0]))
+                    .and(Ok(bits[0] ^ dev.inversion_mask))
+            }
+
+            /// Find the selected pins that stay low when set high, e.g. due to a short
+            /// circuit. Returns the mask of these pins.
+            ///
+            /// The selected pins are set high while reading and their previous status
+            /// is restored afterwards. The other pins keep their status.
+            /// The mask of the pins to be checked can be created with a combination of
+            /// `PinFlag::P0` to `PinFlag::P7`.
+            pub fn stuck_low_pins(&mut self, mask: PinFlag) -> Result<u8, Error<E>> {
+                if (mask.mask >> 8) != 0 {
+                    return Err(Error::InvalidInputData);
+                }
+                self.do_on_acquired(|mut dev| {
+                    let mask = mask.mask as u8;
+                    let last_set_mask = dev.last_set_mask;
+                    let address = dev.address;
+                    let mut bits = [0];
+                    let read = dev
+                        .i2c
+                        .write(address, &[last_set_mask | mask])
+                        .and_then(|_| dev.i2c.read(address, &mut bits));
+                    // restore even if reading failed, the first error is returned
+                    let restore = if mask & !last_set_mask != 0 {
+                        dev.i2c.write(address, &[last_set_mask])
+                    } else {
+                        Ok(())
+                    };
+                    read.and(restore).map_err(Error::I2C)?;
+                    Ok(mask & !bits[0])
+                })
             }
 
             /// Get the status of the selected I/O pins repeatedly and put them in the
@@ -138,7 +275,11 @@ macro_rules! pcf8574 {
                         // configure selected pins as inputs
                         dev.i2c.write(address, &[mask]).map_err(Error::I2C)?;
 
-                        dev.i2c.read(address, &mut data).map_err(Error::I2C)
+                        dev.i2c.read(address, &mut data).map_err(Error::I2C)?;
+                        for bits in data.iter_mut() {
+                            *bits ^= dev.inversion_mask;
+                        }
+                        Ok(())
                     })?;
                 }
                 Ok(())
@@ -1,6 +1,6 @@
 use super::super::split_pins;
 use super::super::{Error, Pcf8574, Pcf8574a, Pcf8575, PinFlag};
-use embedded_hal::i2c::I2c;
+use embedded_hal::{delay::DelayNs, i2c::I2c};
 
 macro_rules! pcf8574_set_pin_impl {
     ( $( $device_name:ident ),+ ) => {
@@ -18,17 +18,35 @@ macro_rules! pcf8574_set_pin_impl {
             {
                 fn set_pin_high(&self, pin_flag: PinFlag) -> Result<(), Error<E>> {
                     self.do_on_acquired(|dev|{
-                    let new_mask = dev.last_set_mask | pin_flag.mask as u8;
+                    let new_mask = if dev.inversion_mask & pin_flag.mask as u8 != 0 {
+                        dev.last_set_mask & !pin_flag.mask as u8
+                    } else {
+                        dev.last_set_mask | pin_flag.mask as u8
+                    };
                     Self::_set(dev, new_mask)
                     })
                 }
 
                 fn set_pin_low(&self, pin_flag: PinFlag) -> Result<(), Error<E>> {
                     self.do_on_acquired(|dev|{
-                    let new_mask = dev.last_set_mask & !pin_flag.mask as u8;
+                    let new_mask = if dev.inversion_mask & pin_flag.mask as u8 != 0 {
+                        dev.last_set_mask | pin_flag.mask as u8
+                    } else {
+                        dev.last_set_mask & !pin_flag.mask as u8
+                    };
                     Self::_set(dev, new_mask)
                     })
                 }
+
+                fn pulse_pin<D: DelayNs>(
+                    &self,
+                    pin_flag: PinFlag,
+                    high: bool,
+                    duration_ns: u32,
+                    delay: &mut D,
+                ) -> Result<(), Error<E>> {
+                    self._pulse(pin_flag, high, duration_ns, delay)
+                }
             }
         )*
     }
@@ -43,15 +61,33 @@ where
 {
     fn set_pin_high(&self, pin_flag: PinFlag) -> Result<(), Error<E>> {
         self.do_on_acquired(|dev| {
-            let new_mask = dev.last_set_mask | pin_flag.mask;
+            let new_mask = if dev.inversion_mask & pin_flag.mask != 0 {
+                dev.last_set_mask & !pin_flag.mask
+            } else {
+                dev.last_set_mask | pin_flag.mask
+            };
             Self::_set(dev, new_mask)
         })
     }
 
     fn set_pin_low(&self, pin_flag: PinFlag) -> Result<(), Error<E>> {
         self.do_on_acquired(|dev| {
-            let new_mask = dev.last_set_mask & !pin_flag.mask;
+            let new_mask = if dev.inversion_mask & pin_flag.mask != 0 {
+                dev.last_set_mask | pin_flag.mask
+            } else {
+                dev.last_set_mask & !pin_flag.mask
+            };
             Self::_set(dev, new_mask)
         })
     }
+
+    fn pulse_pin<D: DelayNs>(
+        &self,
+        pin_flag: PinFlag,
+        high: bool,
+        duration_ns: u32,
+        delay: &mut D,
+    ) -> Result<(), Error<E>> {
+        self._pulse(pin_flag, high, duration_ns, delay)
+    }
 }
@@ -0,0 +1,275 @@
+//! Quadrature rotary encoders
+//!
+//! `Encoders` decodes several incremental rotary encoders connected to input pins of
+//! the same device from port snapshots. The snapshots can be read with `poll()`
+//! periodically or when the INT output of the device is triggered, or passed to
+//! `update()` when the port is read elsewhere. Each encoder needs at
+//! least one snapshot per state change, so the port must be read often enough while
+//! the knob turns.
+//!
+//! The A/B signals are decoded with a state table. Transitions where both signals
+//! change at once are invalid (a state change was missed) and are ignored, so that
+//! contact bounce does not produce spurious steps. The number of quadrature steps per
+//! detent can be configured (usually 4, sometimes 2 or 1).
+//!
+//! The push switch of an encoder can optionally be tracked as well. Pressed means the
+//! pin level is high. Switches wired active-low (to ground, using the weak pull-up of
+//! the device) can be handled by setting the device inversion mask.
+//! See `set_inversion_mask()`.
+//!
+//! ```no_run
+//! use linux_embedded_hal::I2cdev;
+//! use pcf857x::encoder::{EncoderPins, Encoders};
+//! use pcf857x::{Pcf8574, PinFlag, SlaveAddr};
+//!
+//! let dev = I2cdev::new("/dev/i2c-1").unwrap();
+//! let mut expander = Pcf8574::new(dev, SlaveAddr::default());
+//! expander.set_inversion_mask(0b0010_0100);
+//! let mut encoders = Encoders::new(expander, [
+//!     EncoderPins::new(PinFlag::P0, PinFlag::P1).with_switch(PinFlag::P2),
+//!     EncoderPins::new(PinFlag::P3, PinFlag::P4).with_switch(PinFlag::P5),
+//! ]);
+//! loop {
+//!     encoders.poll().unwrap();
+//!     let volume_change = encoders.take_delta(0).unwrap();
+//!     if encoders.take_pressed(1).unwrap() {
+//!         // ...
+//!     }
+//! }
+//! ```
+
+use crate::{Error, PinFlag, Port};
+
+/// Default number of quadrature steps per detent
+pub const DEFAULT_STEPS_PER_DETENT: u8 = 4;
+
+/// Quadrature step for each transition, indexed by the previous and the
+/// current A/B state (A being the high bit). Steps are positive when A leads B.
+/// `None` marks the invalid transitions.
+const TRANSITIONS: [Option<i8>; 16] = [
+    Some(0),
+    Some(-1),
+    Some(1),
+    None,
+    Some(1),
+    Some(0),
+    None,
+    Some(-1),
+    Some(-1),
+    None,
+    Some(0),
+    Some(1),
+    None,
+    Some(1),
+    Some(-1),
+    Some(0),
+];
+
+/// Pin assignment of an encoder
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EncoderPins {
+    /// Signal A
+    pub a: PinFlag,
+    /// Signal B
+    pub b: PinFlag,
+    /// Push switch. `None` if not connected.
+    pub switch: Option<PinFlag>,
+}
+
+impl EncoderPins {
+    /// Pin assignment of an encoder without push switch
+    pub fn new(a: PinFlag, b: PinFlag) -> Self {
+        EncoderPins { a, b, switch: None }
+    }
+
+    /// Add a push switch to the pin assignment
+    pub fn with_switch(self, switch: PinFlag) -> Self {
+        EncoderPins {
+            switch: Some(switch),
+            ..self
+        }
+    }
+
+    fn mask(&self) -> u16 {
+        self.a.mask | self.b.mask | self.switch.map_or(0, |pin| pin.mask)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Encoder {
+    pins: EncoderPins,
+    steps_per_detent: u8,
+    state: u8,
+    steps: i8,
+    position: i32,
+    delta: i32,
+    invalid_transitions: u32,
+    pressed: bool,
+    press_latched: bool,
+}
+
+impl Encoder {
+    fn update(&mut self, snapshot: u16, initialize: bool) {
+        let a = u8::from(snapshot & self.pins.a.mask != 0);
+        let b = u8::from(snapshot & self.pins.b.mask != 0);
+        let state = (a << 1) | b;
+        let pressed = self.pins.switch.is_some_and(|pin| snapshot & pin.mask != 0);
+        if initialize {
+            self.state = state;
+            self.pressed = pressed;
+            return;
+        }
+        if pressed && !self.pressed {
+            self.press_latched = true;
+        }
+        self.pressed = pressed;
+        match TRANSITIONS[usize::from((self.state << 2) | state)] {
+            Some(step) => {
+                self.state = state;
+                self.steps += step;
+                let detent = self.steps_per_detent as i8;
+                if self.steps.abs() >= detent {
+                    let direction = self.steps.signum();
+                    self.steps -= direction * detent;
+                    self.position = self.position.wrapping_add(i32::from(direction));
+                    self.delta = self.delta.wrapping_add(i32::from(direction));
+                }
+            }
+            None => {
+                self.state = state;
+                self.invalid_transitions = self.invalid_transitions.wrapping_add(1);
+            }
+        }
+    }
+}
+
+/// Decoder for `N` rotary encoders on a device
+#[derive(Debug)]
+pub struct Encoders<P, const N: usize> {
+    port: P,
+    encoders: [Encoder; N],
+    initialized: bool,
+}
+
+impl<P: Port, const N: usize> Encoders<P, N> {
+    /// Create a new decoder with the pins of each encoder. No I²C communication is done.
+    pub fn new(port: P, pins: [EncoderPins; N]) -> Self {
+        Encoders {
+            port,
+            encoders: pins.map(|pins| Encoder {
+                pins,
+                steps_per_detent: DEFAULT_STEPS_PER_DETENT,
+                state: 0,
+                steps: 0,
+                position: 0,
+                delta: 0,
+                invalid_transitions: 0,
+                pressed: false,
+                press_latched: false,
+            }),
+            initialized: false,
+        }
+    }
+
+    /// Destroy the decoder, return the device.
+    pub fn destroy(self) -> P {
+        self.port
+    }
+
+    /// Set the number of quadrature steps per detent of an encoder (1 to 4).
+    ///
+    /// Returns `Error::InvalidInputData` if the encoder does not exist or the value
+    /// is out of range.
+    pub fn set_steps_per_detent(
+        &mut self,
+        encoder: usize,
+        steps: u8,
+    ) -> Result<(), Error<P::BusError>> {
+        if !(1..=4).contains(&steps) {
+            return Err(Error::InvalidInputData);
+        }
+        let encoder = self.encoder(encoder)?;
+        encoder.steps_per_detent = steps;
+        encoder.steps = 0;
+        Ok(())
+    }
+
+    /// Read the pins of all encoders with a single `get()` and process the snapshot.
+    pub fn poll(&mut self) -> Result<(), Error<P::BusError>> {
+        let mask = self
+            .encoders
+            .iter()
+            .fold(0, |mask, encoder| mask | encoder.pins.mask());
+        let snapshot = self.port.get(PinFlag { mask })?;
+        self.update(snapshot);
+        Ok(())
+    }
+
+    /// Process a snapshot of the port, e.g. read by other code sharing the device.
+    ///
+    /// The first snapshot is taken as the initial state.
+    pub fn update(&mut self, snapshot: u16) {
+        let initialize = !self.initialized;
+        self.initialized = true;
+        for encoder in &mut self.encoders {
+            encoder.update(snapshot, initialize);
+        }
+    }
+
+    /// Position of an encoder, in detents
+    pub fn position(&self, encoder: usize) -> Result<i32, Error<P::BusError>> {
+        self.encoders
+            .get(encoder)
+            .map(|encoder| encoder.position)
+            .ok_or(Error::InvalidInputData)
+    }
+
+    /// Set the position of an encoder, in detents.
+    pub fn set_position(
+        &mut self,
+        encoder: usize,
+        position: i32,
+    ) -> Result<(), Error<P::BusError>> {
+        self.encoder(encoder)?.position = position;
+        Ok(())
+    }
+
+    /// Detents an encoder turned since the last call. Negative values are
+    /// counter-clockwise (B leading A).
+    pub fn take_delta(&mut self, encoder: usize) -> Result<i32, Error<P::BusError>> {
+        let encoder = self.encoder(encoder)?;
+        let delta = encoder.delta;
+        encoder.delta = 0;
+        Ok(delta)
+    }
+
+    /// Whether the push switch of an encoder is pressed
+    pub fn is_pressed(&self, encoder: usize) -> Result<bool, Error<P::BusError>> {
+        self.encoders
+            .get(encoder)
+            .map(|encoder| encoder.pressed)
+            .ok_or(Error::InvalidInputData)
+    }
+
+    /// Whether the push switch of an encoder was pressed since the last call
+    pub fn take_pressed(&mut self, encoder: usize) -> Result<bool, Error<P::BusError>> {
+        let encoder = self.encoder(encoder)?;
+        let pressed = encoder.press_latched;
+        encoder.press_latched = false;
+        Ok(pressed)
+    }
+
+    /// Number of invalid transitions detected on an encoder
+    pub fn invalid_transitions(&self, encoder: usize) -> Result<u32, Error<P::BusError>> {
+        self.encoders
+            .get(encoder)
+            .map(|encoder| encoder.invalid_transitions)
+            .ok_or(Error::InvalidInputData)
+    }
+
+    fn encoder(&mut self, encoder: usize) -> Result<&mut Encoder, Error<P::BusError>> {
+        self.encoders
+            .get_mut(encoder)
+            .ok_or(Error::InvalidInputData)
+    }
+}
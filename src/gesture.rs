@@ -0,0 +1,273 @@
+//! Input gesture detection
+//!
+//! A `GestureDetector` turns port snapshots of button inputs into higher-level events:
+//! pressed, released, long-press, repeat, double-click and chords of several pins
+//! pressed together. Events are stored in a fixed-capacity queue of `N` elements,
+//! no allocation is done.
+//!
+//! The snapshots can come from periodic `get()` calls, reads triggered by the INT
+//! output of the device, or a `Debouncer` state. Time is provided by the caller as a
+//! free-running `u32` timestamp or tick count (e.g. milliseconds). Wrap-around is
+//! handled. A pin is considered pressed while its level is high. Timed events like
+//! long-press are only detected when calling `update()`, so it should be called
+//! periodically even if no input changed.
+//!
+//! ```no_run
+//! use linux_embedded_hal::I2cdev;
+//! use pcf857x::{gesture::{Event, GestureDetector, Timings}, Pcf8574, PinFlag, SlaveAddr};
+//!
+//! let dev = I2cdev::new("/dev/i2c-1").unwrap();
+//! let mut expander = Pcf8574::new(dev, SlaveAddr::default());
+//! let mut gestures: GestureDetector<8> =
+//!     GestureDetector::new(PinFlag::P0 | PinFlag::P1, Timings::default());
+//! # let now = 0;
+//! gestures.poll(&mut expander, now).unwrap();
+//! while let Some(event) = gestures.next_event() {
+//!     if event == Event::LongPress(PinFlag::P0) {
+//!         // ...
+//!     }
+//! }
+//! ```
+
+use crate::port::{pins, Port};
+use crate::{Error, PinFlag};
+
+/// Input event
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Event {
+    /// The pin went high
+    Pressed(PinFlag),
+    /// The pin went low
+    Released(PinFlag),
+    /// The pin has been held high for the long-press time
+    LongPress(PinFlag),
+    /// The pin is still held high after a long-press. Emitted every repeat interval.
+    Repeat(PinFlag),
+    /// The pin was pressed a second time within the double-click time after a release
+    DoubleClick(PinFlag),
+    /// Several pins were pressed within the chord time window.
+    /// Contains the combination of all the pins held.
+    Chord(PinFlag),
+}
+
+/// Gesture timings, in the same units as the timestamps passed to `update()`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timings {
+    /// Time a pin must be held for a `LongPress` event
+    pub long_press: u32,
+    /// Time between `Repeat` events after a long-press. `0` disables repeating.
+    pub repeat_interval: u32,
+    /// Maximum time between a release and the next press for a `DoubleClick` event
+    pub double_click: u32,
+    /// Maximum time between the presses of the pins forming a `Chord`
+    pub chord_window: u32,
+}
+
+impl Default for Timings {
+    /// Timings in milliseconds
+    fn default() -> Self {
+        Timings {
+            long_press: 1000,
+            repeat_interval: 200,
+            double_click: 300,
+            chord_window: 50,
+        }
+    }
+}
+
+/// Fixed-capacity event queue
+#[derive(Debug, Clone)]
+pub struct EventQueue<const N: usize> {
+    events: [Option<Event>; N],
+    head: usize,
+    len: usize,
+    overflowed: bool,
+}
+
+impl<const N: usize> Default for EventQueue<N> {
+    fn default() -> Self {
+        EventQueue {
+            events: [None; N],
+            head: 0,
+            len: 0,
+            overflowed: false,
+        }
+    }
+}
+
+impl<const N: usize> EventQueue<N> {
+    /// Create an empty queue.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append an event. If the queue is full, the event is dropped and
+    /// the overflow flag is set.
+    pub fn push(&mut self, event: Event) {
+        if self.len == N {
+            self.overflowed = true;
+        } else {
+            self.events[(self.head + self.len) % N] = Some(event);
+            self.len += 1;
+        }
+    }
+
+    /// Remove and return the oldest event.
+    pub fn pop(&mut self) -> Option<Event> {
+        if self.len == 0 {
+            return None;
+        }
+        let event = self.events[self.head].take();
+        self.head = (self.head + 1) % N;
+        self.len -= 1;
+        event
+    }
+
+    /// Number of queued events
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the queue is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Whether any event was dropped because the queue was full.
+    /// Reading the flag clears it.
+    pub fn take_overflow(&mut self) -> bool {
+        core::mem::replace(&mut self.overflowed, false)
+    }
+}
+
+/// Gesture detector for a group of input pins
+#[derive(Debug, Clone)]
+pub struct GestureDetector<const N: usize> {
+    mask: u16,
+    timings: Timings,
+    queue: EventQueue<N>,
+    initialized: bool,
+    held: u16,
+    pressed_at: [u32; 16],
+    released_at: [u32; 16],
+    last_event_at: [u32; 16],
+    long_pressed: u16,
+    ignored: u16,
+    click_armed: u16,
+    click_suppressed: u16,
+    chord: u16,
+}
+
+impl<const N: usize> GestureDetector<N> {
+    /// Create a new gesture detector for the selected pins.
+    pub fn new(mask: PinFlag, timings: Timings) -> Self {
+        GestureDetector {
+            mask: mask.mask,
+            timings,
+            queue: EventQueue::new(),
+            initialized: false,
+            held: 0,
+            pressed_at: [0; 16],
+            released_at: [0; 16],
+            last_event_at: [0; 16],
+            long_pressed: 0,
+            ignored: 0,
+            click_armed: 0,
+            click_suppressed: 0,
+            chord: 0,
+        }
+    }
+
+    /// Read the selected pins of a device with a single `get()` and process the snapshot.
+    pub fn poll<P: Port>(&mut self, port: &mut P, now: u32) -> Result<(), Error<P::BusError>> {
+        let snapshot = port.get(PinFlag { mask: self.mask })?;
+        self.update(snapshot, now);
+        Ok(())
+    }
+
+    /// Process a snapshot of the port.
+    ///
+    /// The first snapshot is taken as the initial state and produces no events.
+    /// Pins already held then produce no long-press or repeat events until released.
+    pub fn update(&mut self, snapshot: u16, now: u32) {
+        let held = snapshot & self.mask;
+        if !self.initialized {
+            self.initialized = true;
+            self.held = held;
+            self.ignored = held;
+            self.click_suppressed = held;
+            return;
+        }
+        let pressed = held & !self.held;
+        let released = self.held & !held;
+        self.held = held;
+
+        for pin in pins(released) {
+            let bit = 1 << pin;
+            self.queue.push(Event::Released(PinFlag { mask: bit }));
+            self.released_at[pin] = now;
+            if self.long_pressed & bit == 0 && self.click_suppressed & bit == 0 {
+                self.click_armed |= bit;
+            }
+            self.long_pressed &= !bit;
+            self.ignored &= !bit;
+            self.click_suppressed &= !bit;
+        }
+        if held == 0 {
+            self.chord = 0;
+        }
+
+        for pin in pins(pressed) {
+            let bit = 1 << pin;
+            self.queue.push(Event::Pressed(PinFlag { mask: bit }));
+            self.pressed_at[pin] = now;
+            if self.click_armed & bit != 0
+                && now.wrapping_sub(self.released_at[pin]) <= self.timings.double_click
+            {
+                self.queue.push(Event::DoubleClick(PinFlag { mask: bit }));
+                self.click_suppressed |= bit;
+            }
+            self.click_armed &= !bit;
+        }
+        if pressed != 0 && held.count_ones() >= 2 && held != self.chord {
+            let within_window = pins(held)
+                .all(|pin| now.wrapping_sub(self.pressed_at[pin]) <= self.timings.chord_window);
+            if within_window {
+                self.queue.push(Event::Chord(PinFlag { mask: held }));
+                self.chord = held;
+                self.click_suppressed |= held;
+            }
+        }
+
+        for pin in pins(held & !pressed & !self.ignored) {
+            let bit = 1 << pin;
+            if self.long_pressed & bit == 0 {
+                if now.wrapping_sub(self.pressed_at[pin]) >= self.timings.long_press {
+                    self.queue.push(Event::LongPress(PinFlag { mask: bit }));
+                    self.long_pressed |= bit;
+                    self.last_event_at[pin] = now;
+                }
+            } else if self.timings.repeat_interval != 0
+                && now.wrapping_sub(self.last_event_at[pin]) >= self.timings.repeat_interval
+            {
+                self.queue.push(Event::Repeat(PinFlag { mask: bit }));
+                self.last_event_at[pin] = now;
+            }
+        }
+    }
+
+    /// Remove and return the oldest event.
+    pub fn next_event(&mut self) -> Option<Event> {
+        self.queue.pop()
+    }
+
+    /// Event queue
+    pub fn queue(&mut self) -> &mut EventQueue<N> {
+        &mut self.queue
+    }
+
+    /// Mask of the pins currently held
+    pub fn held(&self) -> u16 {
+        self.held
+    }
+}
@@ -0,0 +1,282 @@
+//! Bit-banged I²C master
+//!
+//! The quasi-bidirectional pins of the devices behave like open-drain outputs with a
+//! weak pull-up, so two of them can host a slow secondary I²C bus, e.g. for sensors
+//! whose address conflicts with another device on the main bus. `BitBangI2c`
+//! implements the `I2c` trait from `embedded-hal` using an SCL and an SDA pin.
+//!
+//! The weak pull-up of the device is usually not enough for the bus. Add external
+//! pull-up resistors to SCL and SDA.
+//!
+//! Only 7-bit addresses are supported. Clock stretching is supported by reading SCL
+//! after releasing it until it is high. This can be disabled if no target stretches
+//! the clock, which allows packing the clock edges of each byte written into a single
+//! `write_array()` call. The pins not used by the bus keep their status.
+//!
+//! ```no_run
+//! use embedded_hal::i2c::I2c;
+//! use linux_embedded_hal::I2cdev;
+//! use pcf857x::i2c::{BitBangI2c, Pins};
+//! use pcf857x::{Pcf8574, PinFlag, SlaveAddr};
+//!
+//! let dev = I2cdev::new("/dev/i2c-1").unwrap();
+//! let expander = Pcf8574::new(dev, SlaveAddr::default());
+//! let pins = Pins {
+//!     scl: PinFlag::P0,
+//!     sda: PinFlag::P1,
+//! };
+//! let mut bus = BitBangI2c::new(expander, pins);
+//! let mut temperature = [0; 2];
+//! bus.write_read(0x48, &[0x00], &mut temperature).unwrap();
+//! ```
+
+use crate::port::{Batch, Port};
+use crate::{Error, PinFlag};
+use embedded_hal::i2c::{ErrorKind, ErrorType, I2c, NoAcknowledgeSource, Operation};
+
+/// Default maximum number of SCL reads while waiting for a target stretching the clock
+pub const DEFAULT_CLOCK_STRETCH_READS: u16 = 10;
+
+/// Bit-banged I²C bus errors
+#[derive(Debug)]
+pub enum I2cError<E> {
+    /// Device error
+    Device(Error<E>),
+    /// The target did not acknowledge the address or a data byte
+    NoAcknowledge(NoAcknowledgeSource),
+    /// SCL or SDA was low before a start condition
+    BusBusy,
+    /// SCL was still low after the maximum number of reads
+    ClockStretchTimeout,
+}
+
+impl<E> From<Error<E>> for I2cError<E> {
+    fn from(error: Error<E>) -> Self {
+        I2cError::Device(error)
+    }
+}
+
+impl<E: core::fmt::Debug> embedded_hal::i2c::Error for I2cError<E> {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            I2cError::Device(_) => ErrorKind::Other,
+            I2cError::NoAcknowledge(source) => ErrorKind::NoAcknowledge(*source),
+            I2cError::BusBusy | I2cError::ClockStretchTimeout => ErrorKind::Bus,
+        }
+    }
+}
+
+/// Pin assignment of the bus
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pins {
+    /// Clock line
+    pub scl: PinFlag,
+    /// Data line
+    pub sda: PinFlag,
+}
+
+/// Bit-banged I²C bus
+#[derive(Debug)]
+pub struct BitBangI2c<P> {
+    port: P,
+    pins: Pins,
+    clock_stretch_reads: Option<u16>,
+}
+
+impl<P: Port> BitBangI2c<P> {
+    /// Create a new bus with clock stretching support. No I²C communication is done.
+    pub fn new(port: P, pins: Pins) -> Self {
+        BitBangI2c {
+            port,
+            pins,
+            clock_stretch_reads: Some(DEFAULT_CLOCK_STRETCH_READS),
+        }
+    }
+
+    /// Destroy the bus instance, return the device.
+    pub fn destroy(self) -> P {
+        self.port
+    }
+
+    /// Set the maximum number of SCL reads while waiting for a target stretching
+    /// the clock, or `None` to disable clock stretching support.
+    pub fn set_clock_stretch_reads(&mut self, reads: Option<u16>) {
+        self.clock_stretch_reads = reads;
+    }
+
+    fn drive(
+        &mut self,
+        batch: &mut Batch,
+        scl: bool,
+        sda: bool,
+    ) -> Result<(), I2cError<P::BusError>> {
+        let lines = self.pins.scl.mask | self.pins.sda.mask;
+        let mut word = 0;
+        if scl {
+            word |= self.pins.scl.mask;
+        }
+        if sda {
+            word |= self.pins.sda.mask;
+        }
+        // the lines are driven and released with physical levels
+        word ^= self.port.inversion_mask() & lines;
+        let word = (self.port.last_set() & !lines) | word;
+        Ok(batch.push(&mut self.port, word)?)
+    }
+
+    /// Read the physical level of the selected lines.
+    fn read_lines(&mut self, lines: PinFlag) -> Result<u16, I2cError<P::BusError>> {
+        let bits = self.port.get(lines)? ^ self.port.inversion_mask();
+        Ok(bits & lines.mask)
+    }
+
+    /// Release SCL and wait until it is high.
+    fn clock_high(&mut self, batch: &mut Batch, sda: bool) -> Result<(), I2cError<P::BusError>> {
+        self.drive(batch, true, sda)?;
+        if let Some(reads) = self.clock_stretch_reads {
+            batch.flush(&mut self.port)?;
+            for _ in 0..reads.max(1) {
+                if self.read_lines(self.pins.scl)? != 0 {
+                    return Ok(());
+                }
+            }
+            return Err(I2cError::ClockStretchTimeout);
+        }
+        Ok(())
+    }
+
+    fn write_bit(&mut self, batch: &mut Batch, bit: bool) -> Result<(), I2cError<P::BusError>> {
+        self.drive(batch, false, bit)?;
+        self.clock_high(batch, bit)?;
+        self.drive(batch, false, bit)
+    }
+
+    fn read_bit(&mut self, batch: &mut Batch) -> Result<bool, I2cError<P::BusError>> {
+        self.drive(batch, false, true)?;
+        self.clock_high(batch, true)?;
+        batch.flush(&mut self.port)?;
+        let bit = self.read_lines(self.pins.sda)? != 0;
+        self.drive(batch, false, true)?;
+        Ok(bit)
+    }
+
+    fn write_byte(
+        &mut self,
+        batch: &mut Batch,
+        byte: u8,
+        source: NoAcknowledgeSource,
+    ) -> Result<(), I2cError<P::BusError>> {
+        for bit in (0..8).rev() {
+            self.write_bit(batch, byte & (1 << bit) != 0)?;
+        }
+        if self.read_bit(batch)? {
+            return Err(I2cError::NoAcknowledge(source));
+        }
+        Ok(())
+    }
+
+    fn read_byte(&mut self, batch: &mut Batch, ack: bool) -> Result<u8, I2cError<P::BusError>> {
+        let mut byte = 0;
+        for bit in (0..8).rev() {
+            if self.read_bit(batch)? {
+                byte |= 1 << bit;
+            }
+        }
+        self.write_bit(batch, !ack)?;
+        Ok(byte)
+    }
+
+    fn start(&mut self, batch: &mut Batch, repeated: bool) -> Result<(), I2cError<P::BusError>> {
+        if repeated {
+            self.drive(batch, false, true)?;
+            self.clock_high(batch, true)?;
+        } else {
+            self.drive(batch, true, true)?;
+            batch.flush(&mut self.port)?;
+            let lines = self.pins.scl | self.pins.sda;
+            if self.read_lines(lines)? != lines.mask {
+                return Err(I2cError::BusBusy);
+            }
+        }
+        self.drive(batch, true, false)?;
+        self.drive(batch, false, false)
+    }
+
+    fn stop(&mut self, batch: &mut Batch) -> Result<(), I2cError<P::BusError>> {
+        self.drive(batch, false, false)?;
+        self.clock_high(batch, false)?;
+        self.drive(batch, true, true)?;
+        Ok(batch.flush(&mut self.port)?)
+    }
+
+    fn run(
+        &mut self,
+        batch: &mut Batch,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), I2cError<P::BusError>> {
+        if address > 0x7F {
+            return Err(I2cError::Device(Error::InvalidInputData));
+        }
+        let mut previous_read = None;
+        for i in 0..operations.len() {
+            let read = matches!(operations[i], Operation::Read(_));
+            if previous_read != Some(read) {
+                self.start(batch, previous_read.is_some())?;
+                self.write_byte(
+                    batch,
+                    address << 1 | read as u8,
+                    NoAcknowledgeSource::Address,
+                )?;
+            }
+            // the last byte of consecutive reads is not acknowledged
+            let next_read = matches!(operations.get(i + 1), Some(Operation::Read(_)));
+            match &mut operations[i] {
+                Operation::Read(buffer) => {
+                    let len = buffer.len();
+                    for (j, byte) in buffer.iter_mut().enumerate() {
+                        *byte = self.read_byte(batch, next_read || j + 1 < len)?;
+                    }
+                }
+                Operation::Write(buffer) => {
+                    for byte in buffer.iter() {
+                        self.write_byte(batch, *byte, NoAcknowledgeSource::Data)?;
+                    }
+                }
+            }
+            previous_read = Some(read);
+        }
+        if previous_read.is_some() {
+            self.stop(batch)?;
+        }
+        Ok(())
+    }
+}
+
+impl<P> ErrorType for BitBangI2c<P>
+where
+    P: Port,
+    P::BusError: core::fmt::Debug,
+{
+    type Error = I2cError<P::BusError>;
+}
+
+impl<P> I2c for BitBangI2c<P>
+where
+    P: Port,
+    P::BusError: core::fmt::Debug,
+{
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        let mut batch = Batch::new();
+        let result = self.run(&mut batch, address, operations);
+        if result.is_err() {
+            // best effort, the original error is returned
+            let _ = self.stop(&mut batch);
+        }
+        result
+    }
+}
@@ -0,0 +1,207 @@
+//! Matrix keypad scanning
+//!
+//! The rows of the keypad are connected to pins used as outputs and the columns to
+//! pins used as quasi-bidirectional inputs. Each row is driven low in turn while the
+//! rest stay high, and the columns are read: a column reading low means the key at
+//! that row and column is pressed.
+//!
+//! For each scan, one `set()` and one `get()` is done per row. The rest of the pins
+//! of the device keep their status. The row and column pins work with physical levels,
+//! so the device inversion mask does not apply to them.
+//!
+//! The keypad state is debounced as a whole: a new state is accepted once the scanned
+//! matrix has been stable for the debounce time. Time is provided by the caller as a
+//! free-running `u32` timestamp or tick count (e.g. milliseconds).
+//!
+//! Keypads without diodes suffer from ghosting: when three keys forming the corners of
+//! a rectangle are pressed, the fourth corner reads as pressed as well. Such matrices
+//! are detected and ignored until the ambiguity is resolved. See `is_ghosting()`.
+//!
+//! To avoid polling, the keypad can be put in idle mode by calling `idle()`. All rows
+//! are driven low so that any key press pulls a column low and the device activates
+//! its INT output. Then the keypad can be scanned again.
+//!
+//! ```no_run
+//! use linux_embedded_hal::I2cdev;
+//! use pcf857x::{keypad::{KeyEvent, Keypad}, Pcf8574, PinFlag, SlaveAddr};
+//!
+//! let dev = I2cdev::new("/dev/i2c-1").unwrap();
+//! let expander = Pcf8574::new(dev, SlaveAddr::default());
+//! let rows = [PinFlag::P0, PinFlag::P1, PinFlag::P2, PinFlag::P3];
+//! let columns = [PinFlag::P4, PinFlag::P5, PinFlag::P6, PinFlag::P7];
+//! let keymap = [
+//!     ['1', '2', '3', 'A'],
+//!     ['4', '5', '6', 'B'],
+//!     ['7', '8', '9', 'C'],
+//!     ['*', '0', '#', 'D'],
+//! ];
+//! let mut keypad = Keypad::new(expander, rows, columns, keymap, 20);
+//! # let now = 0;
+//! keypad.scan(now).unwrap();
+//! while let Some(event) = keypad.next_event() {
+//!     if let KeyEvent::Pressed(key) = event {
+//!         println!("Key pressed: {}", key);
+//!     }
+//! }
+//! ```
+
+use crate::{Error, PinFlag, Port};
+
+/// Key state change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyEvent<K> {
+    /// The key was pressed
+    Pressed(K),
+    /// The key was released
+    Released(K),
+}
+
+/// Matrix keypad with `R` rows and `C` columns
+#[derive(Debug)]
+pub struct Keypad<P, K, const R: usize, const C: usize> {
+    port: P,
+    rows: [u16; R],
+    columns: [u16; C],
+    keymap: [[K; C]; R],
+    debounce_time: u32,
+    raw: [u16; R],
+    changed_at: u32,
+    state: [u16; R],
+    reported: [u16; R],
+    ghosting: bool,
+}
+
+impl<P, K, const R: usize, const C: usize> Keypad<P, K, R, C>
+where
+    P: Port,
+    K: Copy,
+{
+    /// Create a new keypad.
+    ///
+    /// `rows` and `columns` contain the pin connected to each row and column.
+    /// `keymap` contains the key code for each row and column.
+    /// `debounce_time` is in the same units as the timestamps passed to `scan()`.
+    pub fn new(
+        port: P,
+        rows: [PinFlag; R],
+        columns: [PinFlag; C],
+        keymap: [[K; C]; R],
+        debounce_time: u32,
+    ) -> Self {
+        Keypad {
+            port,
+            rows: rows.map(|pin| pin.mask),
+            columns: columns.map(|pin| pin.mask),
+            keymap,
+            debounce_time,
+            raw: [0; R],
+            changed_at: 0,
+            state: [0; R],
+            reported: [0; R],
+            ghosting: false,
+        }
+    }
+
+    /// Destroy the keypad, return the device.
+    pub fn destroy(self) -> P {
+        self.port
+    }
+
+    /// Scan the keypad matrix and update the debounced state.
+    ///
+    /// Afterwards all rows are left high.
+    pub fn scan(&mut self, now: u32) -> Result<(), Error<P::BusError>> {
+        let columns_mask = self.columns_mask();
+        let pins = self.rows_mask() | columns_mask;
+        let invert = self.port.inversion_mask() & pins;
+        let idle = (self.port.last_set() & !pins) | (pins ^ invert);
+        let mut raw = [0; R];
+        for (row, pressed) in self.rows.iter().zip(raw.iter_mut()) {
+            self.port.set(idle ^ row)?;
+            let levels = self.port.get(PinFlag { mask: columns_mask })? ^ invert;
+            *pressed = !levels & columns_mask;
+        }
+        self.port.set(idle)?;
+
+        if raw != self.raw {
+            self.raw = raw;
+            self.changed_at = now;
+        }
+        self.ghosting = has_ghosting(&raw);
+        if !self.ghosting && now.wrapping_sub(self.changed_at) >= self.debounce_time {
+            self.state = raw;
+        }
+        Ok(())
+    }
+
+    /// Return the next debounced key state change since the last call, if any.
+    pub fn next_event(&mut self) -> Option<KeyEvent<K>> {
+        for (r, (state, reported)) in self.state.iter().zip(self.reported.iter_mut()).enumerate() {
+            let changed = state ^ *reported;
+            if let Some(c) = self.columns.iter().position(|column| changed & column != 0) {
+                let column = self.columns[c];
+                *reported ^= column;
+                let key = self.keymap[r][c];
+                return Some(if state & column != 0 {
+                    KeyEvent::Pressed(key)
+                } else {
+                    KeyEvent::Released(key)
+                });
+            }
+        }
+        None
+    }
+
+    /// Iterate over the keys currently pressed (debounced).
+    pub fn pressed_keys(&self) -> impl Iterator<Item = K> + '_ {
+        self.state.iter().enumerate().flat_map(move |(r, state)| {
+            self.columns
+                .iter()
+                .enumerate()
+                .filter(move |(_, column)| state & **column != 0)
+                .map(move |(c, _)| self.keymap[r][c])
+        })
+    }
+
+    /// Whether the last scan found an ambiguous key combination (ghosting).
+    /// In this case the debounced state was not updated.
+    pub fn is_ghosting(&self) -> bool {
+        self.ghosting
+    }
+
+    /// Enter idle mode: drive all rows low so that any key press activates
+    /// the INT output of the device.
+    pub fn idle(&mut self) -> Result<(), Error<P::BusError>> {
+        let columns_mask = self.columns_mask();
+        let pins = self.rows_mask() | columns_mask;
+        let invert = self.port.inversion_mask() & pins;
+        let bits = (self.port.last_set() & !pins) | (columns_mask ^ invert);
+        self.port.set(bits)
+    }
+
+    /// Whether any key is pressed, with a single `get()`.
+    /// The keypad must be in idle mode. See `idle()`.
+    pub fn any_key_pressed(&mut self) -> Result<bool, Error<P::BusError>> {
+        let columns_mask = self.columns_mask();
+        let invert = self.port.inversion_mask();
+        let levels = self.port.get(PinFlag { mask: columns_mask })? ^ invert;
+        Ok(levels & columns_mask != columns_mask)
+    }
+
+    fn rows_mask(&self) -> u16 {
+        self.rows.iter().fold(0, |mask, row| mask | row)
+    }
+
+    fn columns_mask(&self) -> u16 {
+        self.columns.iter().fold(0, |mask, column| mask | column)
+    }
+}
+
+/// Two rows sharing two or more pressed columns form a rectangle where
+/// at least one of the corners may be a phantom key.
+fn has_ghosting(matrix: &[u16]) -> bool {
+    matrix
+        .iter()
+        .enumerate()
+        .any(|(i, a)| matrix[i + 1..].iter().any(|b| (a & b).count_ones() >= 2))
+}
@@ -0,0 +1,520 @@
+//! HD44780 character LCD
+//!
+//! Driver for HD44780 (and compatible) character LCDs connected to the I/O pins of a
+//! device, like the common PCF8574(A) backpack modules.
+//!
+//! The display is controlled through a data bus:
+//! - `FourBitBus`: 4-bit interface. The RS, RW, E, backlight and D4-D7 lines can be
+//!   connected to any pins. `FourBitPins::default()` matches the usual backpack wiring.
+//! - `EightBitBus`: 8-bit interface, only for PCF8575 devices. The RS, RW, E, backlight
+//!   and D0-D7 lines can be connected to any pins. This halves the bus traffic per
+//!   character.
+//!
+//! In 4-bit mode each byte is sent as two nibble strobes (E high, E low), in 8-bit mode
+//! as a single strobe. The strobes of a whole command or text are batched into as few
+//! `write_array()` calls as possible. The pins not used by the display keep their status.
+//!
+//! Normal commands need no additional delay since the I²C transfer of the next byte
+//! takes longer than their execution time with I²C bus frequencies up to 400 kHz.
+//! Initialization, `clear()` and `home()` take a `DelayNs` implementation.
+//!
+//! ```no_run
+//! use linux_embedded_hal::{Delay, I2cdev};
+//! use pcf857x::{lcd::{FourBitBus, FourBitPins, Lcd}, Pcf8574, SlaveAddr};
+//!
+//! let dev = I2cdev::new("/dev/i2c-1").unwrap();
+//! let expander = Pcf8574::new(dev, SlaveAddr::default());
+//! let bus = FourBitBus::new(expander, FourBitPins::default());
+//! let mut lcd = Lcd::new(bus, 16, 2);
+//! lcd.init(&mut Delay).unwrap();
+//! lcd.set_backlight(true).unwrap();
+//! lcd.write_str("Hello, world!").unwrap();
+//! lcd.set_cursor(0, 1).unwrap();
+//! lcd.write_str("Line 2").unwrap();
+//! ```
+//!
+//! Using the 8-bit interface:
+//!
+//! ```no_run
+//! use linux_embedded_hal::{Delay, I2cdev};
+//! use pcf857x::{lcd::{EightBitBus, EightBitPins, Lcd}, Pcf8575, SlaveAddr};
+//!
+//! let dev = I2cdev::new("/dev/i2c-1").unwrap();
+//! let expander = Pcf8575::new(dev, SlaveAddr::default());
+//! let bus = EightBitBus::new(expander, EightBitPins::default());
+//! let mut lcd = Lcd::new(bus, 20, 4);
+//! lcd.init(&mut Delay).unwrap();
+//! lcd.write_str("Hello, world!").unwrap();
+//! ```
+
+use crate::port::{encode_word, Port, WidePort};
+use crate::{Error, PinFlag};
+use embedded_hal::delay::DelayNs;
+
+const CLEAR_DISPLAY: u8 = 0x01;
+const RETURN_HOME: u8 = 0x02;
+const ENTRY_MODE_SET: u8 = 0x04;
+const ENTRY_INCREMENT: u8 = 0x02;
+const DISPLAY_CONTROL: u8 = 0x08;
+const DISPLAY_ON: u8 = 0x04;
+const CURSOR_ON: u8 = 0x02;
+const BLINK_ON: u8 = 0x01;
+const SHIFT: u8 = 0x10;
+const SHIFT_DISPLAY: u8 = 0x08;
+const SHIFT_RIGHT: u8 = 0x04;
+const FUNCTION_SET: u8 = 0x20;
+const EIGHT_BIT_MODE: u8 = 0x10;
+const TWO_LINES: u8 = 0x08;
+const SET_CGRAM_ADDRESS: u8 = 0x40;
+const SET_DDRAM_ADDRESS: u8 = 0x80;
+
+/// Data bus connecting the display.
+///
+/// This is implemented by the bus types in this module.
+pub trait DataBus {
+    /// I²C bus error
+    type BusError;
+
+    /// Whether the bus is 8 bits wide
+    const EIGHT_BIT: bool;
+
+    /// Send the power-on wake-up sequence selecting the bus width.
+    fn wake_up<D: DelayNs>(&mut self, delay: &mut D) -> Result<(), Error<Self::BusError>>;
+
+    /// Send bytes as commands (`data` is `false`) or as data (`data` is `true`).
+    fn write(&mut self, data: bool, bytes: &[u8]) -> Result<(), Error<Self::BusError>>;
+
+    /// Switch the backlight on or off.
+    fn set_backlight(&mut self, on: bool) -> Result<(), Error<Self::BusError>>;
+}
+
+/// Pin assignment for the 4-bit interface
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FourBitPins {
+    /// Register select
+    pub rs: PinFlag,
+    /// Read/write. The display is only written so this is always kept low.
+    /// `None` if the line is tied to ground.
+    pub rw: Option<PinFlag>,
+    /// Enable
+    pub e: PinFlag,
+    /// Backlight, active high. `None` if not connected.
+    pub backlight: Option<PinFlag>,
+    /// Data lines D4 to D7
+    pub data: [PinFlag; 4],
+}
+
+impl Default for FourBitPins {
+    /// Usual PCF8574 backpack wiring: RS on P0, RW on P1, E on P2, backlight on P3
+    /// and D4-D7 on P4-P7.
+    fn default() -> Self {
+        FourBitPins {
+            rs: PinFlag::P0,
+            rw: Some(PinFlag::P1),
+            e: PinFlag::P2,
+            backlight: Some(PinFlag::P3),
+            data: [PinFlag::P4, PinFlag::P5, PinFlag::P6, PinFlag::P7],
+        }
+    }
+}
+
+impl FourBitPins {
+    fn mask(&self) -> u16 {
+        let rw = self.rw.map_or(0, |pin| pin.mask);
+        let backlight = self.backlight.map_or(0, |pin| pin.mask);
+        self.data
+            .iter()
+            .fold(self.rs.mask | rw | self.e.mask | backlight, |mask, pin| {
+                mask | pin.mask
+            })
+    }
+
+    fn nibble(&self, value: u8) -> u16 {
+        self.data
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| value & (1 << i) != 0)
+            .fold(0, |word, (_, pin)| word | pin.mask)
+    }
+}
+
+/// 4-bit data bus over any device
+#[derive(Debug)]
+pub struct FourBitBus<P> {
+    port: P,
+    pins: FourBitPins,
+    backlight: bool,
+}
+
+impl<P: Port> FourBitBus<P> {
+    /// Create a new 4-bit bus.
+    pub fn new(port: P, pins: FourBitPins) -> Self {
+        FourBitBus {
+            port,
+            pins,
+            backlight: false,
+        }
+    }
+
+    /// Destroy the bus, return the device.
+    pub fn destroy(self) -> P {
+        self.port
+    }
+
+    fn base(&mut self, data: bool) -> u16 {
+        let base = self.port.last_set() & !self.pins.mask();
+        control_word(
+            base,
+            self.pins.rs,
+            data,
+            self.pins.backlight,
+            self.backlight,
+        )
+    }
+
+    fn write_nibbles(
+        &mut self,
+        data: bool,
+        nibbles: impl Iterator<Item = u8>,
+    ) -> Result<(), Error<P::BusError>> {
+        let base = self.base(data);
+        let pins = self.pins;
+        let words = nibbles.map(|nibble| base | pins.nibble(nibble));
+        write_strobes(&mut self.port, pins.e.mask, words)
+    }
+}
+
+impl<P: Port> DataBus for FourBitBus<P> {
+    type BusError = P::BusError;
+    const EIGHT_BIT: bool = false;
+
+    fn wake_up<D: DelayNs>(&mut self, delay: &mut D) -> Result<(), Error<Self::BusError>> {
+        delay.delay_ms(50);
+        self.write_nibbles(false, core::iter::once(0x3))?;
+        delay.delay_us(4500);
+        self.write_nibbles(false, core::iter::once(0x3))?;
+        delay.delay_us(150);
+        self.write_nibbles(false, core::iter::once(0x3))?;
+        self.write_nibbles(false, core::iter::once(0x2))
+    }
+
+    fn write(&mut self, data: bool, bytes: &[u8]) -> Result<(), Error<Self::BusError>> {
+        let nibbles = bytes.iter().flat_map(|byte| [byte >> 4, byte & 0xF]);
+        self.write_nibbles(data, nibbles)
+    }
+
+    fn set_backlight(&mut self, on: bool) -> Result<(), Error<Self::BusError>> {
+        self.backlight = on;
+        let word = self.base(false);
+        self.port.set(word)
+    }
+}
+
+/// Pin assignment for the 8-bit interface
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EightBitPins {
+    /// Register select
+    pub rs: PinFlag,
+    /// Read/write. The display is only written so this is always kept low.
+    /// `None` if the line is tied to ground.
+    pub rw: Option<PinFlag>,
+    /// Enable
+    pub e: PinFlag,
+    /// Backlight, active high. `None` if not connected.
+    pub backlight: Option<PinFlag>,
+    /// Data lines D0 to D7
+    pub data: [PinFlag; 8],
+}
+
+impl Default for EightBitPins {
+    /// D0-D7 on P0-P7, RS on P10, RW on P11, E on P12 and backlight on P13.
+    fn default() -> Self {
+        EightBitPins {
+            rs: PinFlag::P10,
+            rw: Some(PinFlag::P11),
+            e: PinFlag::P12,
+            backlight: Some(PinFlag::P13),
+            data: [
+                PinFlag::P0,
+                PinFlag::P1,
+                PinFlag::P2,
+                PinFlag::P3,
+                PinFlag::P4,
+                PinFlag::P5,
+                PinFlag::P6,
+                PinFlag::P7,
+            ],
+        }
+    }
+}
+
+impl EightBitPins {
+    fn mask(&self) -> u16 {
+        let rw = self.rw.map_or(0, |pin| pin.mask);
+        let backlight = self.backlight.map_or(0, |pin| pin.mask);
+        self.data
+            .iter()
+            .fold(self.rs.mask | rw | self.e.mask | backlight, |mask, pin| {
+                mask | pin.mask
+            })
+    }
+
+    fn byte(&self, value: u8) -> u16 {
+        self.data
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| value & (1 << i) != 0)
+            .fold(0, |word, (_, pin)| word | pin.mask)
+    }
+}
+
+/// 8-bit data bus over a PCF8575 device
+#[derive(Debug)]
+pub struct EightBitBus<P> {
+    port: P,
+    pins: EightBitPins,
+    backlight: bool,
+}
+
+impl<P: WidePort> EightBitBus<P> {
+    /// Create a new 8-bit bus.
+    pub fn new(port: P, pins: EightBitPins) -> Self {
+        EightBitBus {
+            port,
+            pins,
+            backlight: false,
+        }
+    }
+
+    /// Destroy the bus, return the device.
+    pub fn destroy(self) -> P {
+        self.port
+    }
+
+    fn base(&mut self, data: bool) -> u16 {
+        let base = self.port.last_set() & !self.pins.mask();
+        control_word(
+            base,
+            self.pins.rs,
+            data,
+            self.pins.backlight,
+            self.backlight,
+        )
+    }
+
+    fn write_bytes(&mut self, data: bool, bytes: &[u8]) -> Result<(), Error<P::BusError>> {
+        let base = self.base(data);
+        let pins = self.pins;
+        let words = bytes.iter().map(|byte| base | pins.byte(*byte));
+        write_strobes(&mut self.port, pins.e.mask, words)
+    }
+}
+
+impl<P: WidePort> DataBus for EightBitBus<P> {
+    type BusError = P::BusError;
+    const EIGHT_BIT: bool = true;
+
+    fn wake_up<D: DelayNs>(&mut self, delay: &mut D) -> Result<(), Error<P::BusError>> {
+        delay.delay_ms(50);
+        self.write_bytes(false, &[FUNCTION_SET | EIGHT_BIT_MODE])?;
+        delay.delay_us(4500);
+        self.write_bytes(false, &[FUNCTION_SET | EIGHT_BIT_MODE])?;
+        delay.delay_us(150);
+        self.write_bytes(false, &[FUNCTION_SET | EIGHT_BIT_MODE])
+    }
+
+    fn write(&mut self, data: bool, bytes: &[u8]) -> Result<(), Error<P::BusError>> {
+        self.write_bytes(data, bytes)
+    }
+
+    fn set_backlight(&mut self, on: bool) -> Result<(), Error<P::BusError>> {
+        self.backlight = on;
+        let word = self.base(false);
+        self.port.set(word)
+    }
+}
+
+fn control_word(
+    base: u16,
+    rs: PinFlag,
+    data: bool,
+    backlight_pin: Option<PinFlag>,
+    backlight: bool,
+) -> u16 {
+    let mut word = base;
+    if data {
+        word |= rs.mask;
+    }
+    if let (true, Some(pin)) = (backlight, backlight_pin) {
+        word |= pin.mask;
+    }
+    word
+}
+
+/// Write an enable strobe (E high, E low) for each word, batching as many
+/// strobes as possible into each `write_array()` call.
+fn write_strobes<P: Port>(
+    port: &mut P,
+    e: u16,
+    words: impl Iterator<Item = u16>,
+) -> Result<(), Error<P::BusError>> {
+    let mut buffer = [0; 64];
+    let mut len = 0;
+    for word in words {
+        encode_word::<P>(word | e, &mut buffer[len..]);
+        len += P::WORD_SIZE;
+        encode_word::<P>(word, &mut buffer[len..]);
+        len += P::WORD_SIZE;
+        if len == buffer.len() {
+            port.write_array(&buffer)?;
+            len = 0;
+        }
+    }
+    port.write_array(&buffer[..len])
+}
+
+/// HD44780 character LCD
+#[derive(Debug)]
+pub struct Lcd<B> {
+    bus: B,
+    columns: u8,
+    rows: u8,
+    display_control: u8,
+}
+
+impl<B: DataBus> Lcd<B> {
+    /// Create a new display instance with the given number of columns and rows.
+    pub fn new(bus: B, columns: u8, rows: u8) -> Self {
+        Lcd {
+            bus,
+            columns,
+            rows,
+            display_control: DISPLAY_ON,
+        }
+    }
+
+    /// Destroy the display instance, return the data bus.
+    pub fn destroy(self) -> B {
+        self.bus
+    }
+
+    /// Initialize the display.
+    ///
+    /// Afterwards, the display is on and cleared, the cursor is hidden and
+    /// it moves to the right when writing.
+    pub fn init<D: DelayNs>(&mut self, delay: &mut D) -> Result<(), Error<B::BusError>> {
+        self.bus.wake_up(delay)?;
+        let mut function_set = FUNCTION_SET;
+        if B::EIGHT_BIT {
+            function_set |= EIGHT_BIT_MODE;
+        }
+        if self.rows > 1 {
+            function_set |= TWO_LINES;
+        }
+        self.display_control = DISPLAY_ON;
+        self.bus.write(
+            false,
+            &[
+                function_set,
+                DISPLAY_CONTROL,
+                ENTRY_MODE_SET | ENTRY_INCREMENT,
+                DISPLAY_CONTROL | self.display_control,
+            ],
+        )?;
+        self.clear(delay)
+    }
+
+    /// Clear the display and move the cursor to the top-left position.
+    pub fn clear<D: DelayNs>(&mut self, delay: &mut D) -> Result<(), Error<B::BusError>> {
+        self.bus.write(false, &[CLEAR_DISPLAY])?;
+        delay.delay_us(2000);
+        Ok(())
+    }
+
+    /// Move the cursor to the top-left position and undo any display shift.
+    pub fn home<D: DelayNs>(&mut self, delay: &mut D) -> Result<(), Error<B::BusError>> {
+        self.bus.write(false, &[RETURN_HOME])?;
+        delay.delay_us(2000);
+        Ok(())
+    }
+
+    /// Move the cursor to the given column and row (starting at 0).
+    ///
+    /// Returns `Error::InvalidInputData` if the position is outside of the display.
+    pub fn set_cursor(&mut self, column: u8, row: u8) -> Result<(), Error<B::BusError>> {
+        if column >= self.columns || row >= self.rows {
+            return Err(Error::InvalidInputData);
+        }
+        let row_offset = [0x00, 0x40, self.columns, 0x40 + self.columns][usize::from(row % 4)];
+        self.bus
+            .write(false, &[SET_DDRAM_ADDRESS | (row_offset + column)])
+    }
+
+    /// Write text at the cursor position.
+    ///
+    /// The bytes are sent as they are, so only ASCII characters and the
+    /// display character set can be represented.
+    pub fn write_str(&mut self, text: &str) -> Result<(), Error<B::BusError>> {
+        self.write_bytes(text.as_bytes())
+    }
+
+    /// Write character codes at the cursor position.
+    /// Codes 0 to 7 correspond to the custom characters.
+    pub fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), Error<B::BusError>> {
+        self.bus.write(true, bytes)
+    }
+
+    /// Define a custom character (5x8 dots, one byte per row).
+    ///
+    /// `index` must be between 0 and 7. Afterwards, the cursor position must be
+    /// set again before writing text.
+    pub fn create_char(&mut self, index: u8, rows: [u8; 8]) -> Result<(), Error<B::BusError>> {
+        if index > 7 {
+            return Err(Error::InvalidInputData);
+        }
+        self.bus.write(false, &[SET_CGRAM_ADDRESS | (index << 3)])?;
+        self.bus.write(true, &rows)
+    }
+
+    /// Switch the display on or off. The contents are kept.
+    pub fn set_display(&mut self, on: bool) -> Result<(), Error<B::BusError>> {
+        self.update_display_control(DISPLAY_ON, on)
+    }
+
+    /// Show or hide the cursor.
+    pub fn set_cursor_visible(&mut self, visible: bool) -> Result<(), Error<B::BusError>> {
+        self.update_display_control(CURSOR_ON, visible)
+    }
+
+    /// Enable or disable the cursor blinking.
+    pub fn set_cursor_blink(&mut self, blink: bool) -> Result<(), Error<B::BusError>> {
+        self.update_display_control(BLINK_ON, blink)
+    }
+
+    /// Shift the whole display contents one position to the left or right.
+    pub fn shift_display(&mut self, right: bool) -> Result<(), Error<B::BusError>> {
+        let direction = if right { SHIFT_RIGHT } else { 0 };
+        self.bus.write(false, &[SHIFT | SHIFT_DISPLAY | direction])
+    }
+
+    /// Switch the backlight on or off.
+    pub fn set_backlight(&mut self, on: bool) -> Result<(), Error<B::BusError>> {
+        self.bus.set_backlight(on)
+    }
+
+    fn update_display_control(&mut self, flag: u8, enable: bool) -> Result<(), Error<B::BusError>> {
+        if enable {
+            self.display_control |= flag;
+        } else {
+            self.display_control &= !flag;
+        }
+        self.bus
+            .write(false, &[DISPLAY_CONTROL | self.display_control])
+    }
+}
+
+impl<B: DataBus> core::fmt::Write for Lcd<B> {
+    fn write_str(&mut self, text: &str) -> core::fmt::Result {
+        Lcd::write_str(self, text).map_err(|_| core::fmt::Error)
+    }
+}
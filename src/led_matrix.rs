@@ -0,0 +1,158 @@
+//! 8x8 LED matrix
+//!
+//! An 8x8 LED matrix fits exactly on a PCF8575: rows on P0-P7 and columns on P10-P17.
+//! The pixels are kept in a framebuffer. The matrix is refreshed by calling `tick()`
+//! periodically (e.g. every 1-2 ms): each call shows the next row with a single `set()`.
+//!
+//! Row and column lines can be active-high or active-low, depending on the matrix
+//! type and driver transistors used.
+//!
+//! When the `graphics` feature is enabled, the matrix implements the `DrawTarget` trait
+//! of [`embedded-graphics`], so that text and shapes can be rendered onto it.
+//!
+//! [`embedded-graphics`]: https://crates.io/crates/embedded-graphics
+//!
+//! ```no_run
+//! use linux_embedded_hal::I2cdev;
+//! use pcf857x::{led_matrix::{LedMatrix, Polarity}, Pcf8575, SlaveAddr};
+//!
+//! let dev = I2cdev::new("/dev/i2c-1").unwrap();
+//! let expander = Pcf8575::new(dev, SlaveAddr::default());
+//! let mut matrix = LedMatrix::new(expander, Polarity::ActiveHigh, Polarity::ActiveLow);
+//! matrix.set_pixel(0, 0, true);
+//! matrix.set_pixel(7, 7, true);
+//! loop {
+//!     matrix.tick().unwrap();
+//!     // wait a millisecond
+//! }
+//! ```
+
+use crate::{Error, WidePort};
+
+pub use crate::seven_segment::Polarity;
+
+/// 8x8 LED matrix with rows on P0-P7 and columns on P10-P17 of a PCF8575
+#[derive(Debug)]
+pub struct LedMatrix<P> {
+    port: P,
+    row_polarity: Polarity,
+    column_polarity: Polarity,
+    framebuffer: [u8; 8],
+    current: usize,
+}
+
+impl<P: WidePort> LedMatrix<P> {
+    /// Create a new LED matrix. All pixels are off.
+    pub fn new(port: P, row_polarity: Polarity, column_polarity: Polarity) -> Self {
+        LedMatrix {
+            port,
+            row_polarity,
+            column_polarity,
+            framebuffer: [0; 8],
+            current: 0,
+        }
+    }
+
+    /// Destroy the LED matrix instance, return the device.
+    pub fn destroy(self) -> P {
+        self.port
+    }
+
+    /// Switch a pixel on or off. `x` is the column and `y` the row.
+    /// Pixels outside of the matrix are ignored.
+    pub fn set_pixel(&mut self, x: usize, y: usize, on: bool) {
+        if let (true, Some(row)) = (x < 8, self.framebuffer.get_mut(y)) {
+            if on {
+                *row |= 1 << x;
+            } else {
+                *row &= !(1 << x);
+            }
+        }
+    }
+
+    /// Whether a pixel is on. Pixels outside of the matrix are off.
+    pub fn pixel(&self, x: usize, y: usize) -> bool {
+        x < 8
+            && self
+                .framebuffer
+                .get(y)
+                .is_some_and(|row| row & (1 << x) != 0)
+    }
+
+    /// Framebuffer. Each byte is a row, where bit `x` is the pixel of column `x`.
+    pub fn framebuffer(&self) -> &[u8; 8] {
+        &self.framebuffer
+    }
+
+    /// Framebuffer, for direct modification. See `framebuffer()`.
+    pub fn framebuffer_mut(&mut self) -> &mut [u8; 8] {
+        &mut self.framebuffer
+    }
+
+    /// Switch all pixels off.
+    pub fn clear(&mut self) {
+        self.framebuffer = [0; 8];
+    }
+
+    /// Show the next row. This does a single `set()`.
+    pub fn tick(&mut self) -> Result<(), Error<P::BusError>> {
+        let row = self.row_polarity.apply(1 << self.current, 0xFF);
+        let columns = self
+            .column_polarity
+            .apply(u16::from(self.framebuffer[self.current]), 0xFF);
+        self.current = (self.current + 1) % 8;
+        self.port.set(row | (columns << 8))
+    }
+
+    /// Switch all rows off until the next `tick()`.
+    pub fn blank(&mut self) -> Result<(), Error<P::BusError>> {
+        let row = self.row_polarity.apply(0, 0xFF);
+        let columns = self.column_polarity.apply(0, 0xFF);
+        self.port.set(row | (columns << 8))
+    }
+}
+
+#[cfg(feature = "graphics")]
+mod graphics {
+    use super::LedMatrix;
+    use core::convert::Infallible;
+    use embedded_graphics_core::{
+        draw_target::DrawTarget,
+        geometry::{OriginDimensions, Size},
+        pixelcolor::BinaryColor,
+        Pixel,
+    };
+
+    impl<P> OriginDimensions for LedMatrix<P> {
+        fn size(&self) -> Size {
+            Size::new(8, 8)
+        }
+    }
+
+    impl<P> DrawTarget for LedMatrix<P> {
+        type Color = BinaryColor;
+        type Error = Infallible;
+
+        fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+        where
+            I: IntoIterator<Item = Pixel<Self::Color>>,
+        {
+            for Pixel(point, color) in pixels {
+                if (0..8).contains(&point.x) && (0..8).contains(&point.y) {
+                    let row = &mut self.framebuffer[point.y as usize];
+                    match color {
+                        BinaryColor::On => *row |= 1 << point.x,
+                        BinaryColor::Off => *row &= !(1 << point.x),
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+            let row = if color.is_on() { 0xFF } else { 0 };
+            self.framebuffer = [row; 8];
+            Ok(())
+        }
+    }
+}
@@ -9,6 +9,29 @@
 //! - Set all the outputs repeatedly looping through an array. See `write_array()`.
 //! - Read selected inputs repeatedly filling up an array. See `read_array()`.
 //! - Split the device into individual input/output pins. See `split()`.
+//! - Invert the logic of selected pins for active-low wiring. See `set_inversion_mask()`.
+//! - Pulse reset or enable lines for a given time. See `pulse()`.
+//! - Detect outputs stuck low by reading them back. See `set_verify()` and `stuck_low_pins()`.
+//! - Debounce buttons connected to input pins. See the `debounce` module.
+//! - Detect button gestures like long-press or double-click. See the `gesture` module.
+//! - Scan matrix keypads. See the `keypad` module.
+//! - Drive HD44780 character LCDs in 4-bit or 8-bit mode. See the `lcd` module.
+//! - Drive multiplexed seven-segment displays. See the `seven_segment` module.
+//! - Scan 8x8 LED matrices, optionally drawing with `embedded-graphics`. See the `led_matrix` module.
+//! - Drive unipolar and bipolar stepper motors. See the `stepper` module.
+//! - Drive relay banks with interlocks and a fail-safe state. See the `relay` module.
+//! - Dim LEDs with software PWM. See the `pwm` module.
+//! - Play blink patterns and status codes on LEDs. See the `blink` module.
+//! - Talk to SPI peripherals with a bit-banged SPI master. See the `spi` module.
+//! - Host a secondary bit-banged I²C bus. See the `i2c` module.
+//! - Extend the outputs and inputs with 74HC595 and 74HC165 shift register chains. See the `shift_register` module.
+//! - Write to parallel interfaces with STROBE/BUSY/ACK handshaking, optionally through `embedded-io`. See the `parallel` module.
+//! - Decode quadrature rotary encoders with push switches. See the `encoder` module.
+//! - Read DIP switches, BCD thumbwheels and Gray code inputs. See the `code_reader` module.
+//! - Count pulses and measure their frequency. See the `counter` module.
+//! - Sequence power rails with power-good checks and rollback. See the `power` module.
+//! - Put the chip selects of SPI peripherals on a shared bus on the device. See the `chip_select` module.
+//! - Check loopback fixtures for opens and shorts in end-of-line tests. See the `self_test` module.
 //!
 //! ## The devices
 //! The devices consist of 8 or 16 quasi-bidirectional ports, I²C-bus interface, three
@@ -146,14 +169,56 @@ pub enum Error<E> {
     InvalidInputData,
     /// Could not acquire device. Maybe it is already acquired.
     CouldNotAcquireDevice,
+    /// Output pins set high read back low, e.g. due to a short circuit.
+    /// Contains the mask of these pins. See `set_verify()`.
+    OutputMismatch(u16),
 }
 
+impl<E: embedded_hal::i2c::Error> Error<E> {
+    /// Kind of the I²C bus error, e.g. `NoAcknowledge` if the device is not connected.
+    ///
+    /// Returns `None` for errors other than `Error::I2C`.
+    pub fn i2c_kind(&self) -> Option<embedded_hal::i2c::ErrorKind> {
+        match self {
+            Error::I2C(error) => Some(error.kind()),
+            _ => None,
+        }
+    }
+}
+
+impl<E: core::fmt::Debug> core::fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Error::I2C(error) => write!(f, "I²C bus error: {:?}", error),
+            Error::InvalidInputData => write!(f, "invalid input data"),
+            Error::CouldNotAcquireDevice => write!(f, "could not acquire device"),
+            Error::OutputMismatch(pins) => {
+                write!(f, "output pins set high read back low: {:#06x}", pins)
+            }
+        }
+    }
+}
+
+impl<E: core::fmt::Debug> core::error::Error for Error<E> {}
+
 impl<E: core::fmt::Debug> embedded_hal::digital::Error for Error<E> {
     fn kind(&self) -> embedded_hal::digital::ErrorKind {
         embedded_hal::digital::ErrorKind::Other
     }
 }
 
+impl<E: core::fmt::Debug> embedded_hal::pwm::Error for Error<E> {
+    fn kind(&self) -> embedded_hal::pwm::ErrorKind {
+        embedded_hal::pwm::ErrorKind::Other
+    }
+}
+
+impl<E: core::fmt::Debug> embedded_hal::spi::Error for Error<E> {
+    fn kind(&self) -> embedded_hal::spi::ErrorKind {
+        embedded_hal::spi::ErrorKind::Other
+    }
+}
+
 mod slave_addr;
 pub use crate::slave_addr::SlaveAddr;
 mod pin_flag;
@@ -165,3 +230,25 @@ pub use crate::split_pins::{
 mod devices;
 pub use crate::devices::pcf8574::{Pcf8574, Pcf8574a};
 pub use crate::devices::pcf8575::Pcf8575;
+mod port;
+pub use crate::port::{Port, WidePort};
+pub mod blink;
+pub mod chip_select;
+pub mod code_reader;
+pub mod counter;
+pub mod debounce;
+pub mod encoder;
+pub mod gesture;
+pub mod i2c;
+pub mod keypad;
+pub mod lcd;
+pub mod led_matrix;
+pub mod parallel;
+pub mod power;
+pub mod pwm;
+pub mod relay;
+pub mod self_test;
+pub mod seven_segment;
+pub mod shift_register;
+pub mod spi;
+pub mod stepper;
@@ -0,0 +1,197 @@
+//! Parallel data bus with handshaking
+//!
+//! Writes to legacy equipment with an 8-bit parallel interface and STROBE/BUSY/ACK
+//! handshaking (Centronics-style) through a PCF8575. The data lines D0-D7 are
+//! connected to P0-P7 and the control lines to pins of the upper byte.
+//!
+//! For each byte, the data is put on the bus and STROBE is pulsed low.
+//! If a BUSY line is connected, it is polled with `get()` before each byte until
+//! the receiver is ready. If an ACK line is connected, it is polled with `get()`
+//! after each byte until the receiver acknowledges it. The ACK pulse is usually very
+//! short compared to an I²C transfer, so it is only useful for slow receivers.
+//! Without handshake lines, the strobes for all bytes are packed into as few
+//! `write_array()` calls as possible. The pins not used by the bus keep their status.
+//!
+//! When the `io` feature is enabled, `ParallelBus` implements the `Write` trait of
+//! [`embedded-io`] for streaming data.
+//!
+//! [`embedded-io`]: https://crates.io/crates/embedded-io
+//!
+//! ```no_run
+//! use linux_embedded_hal::I2cdev;
+//! use pcf857x::{parallel::{ParallelBus, Pins}, Pcf8575, SlaveAddr};
+//!
+//! let dev = I2cdev::new("/dev/i2c-1").unwrap();
+//! let expander = Pcf8575::new(dev, SlaveAddr::default());
+//! let mut printer = ParallelBus::new(expander, Pins::default());
+//! printer.write(b"Hello, world!\r\n").unwrap();
+//! ```
+
+use crate::port::{Batch, WidePort};
+use crate::{Error, PinFlag};
+
+/// Default maximum number of BUSY or ACK polls per byte
+pub const DEFAULT_MAX_POLLS: u32 = 1000;
+
+/// Parallel bus errors
+#[derive(Debug)]
+pub enum ParallelError<E> {
+    /// Device error
+    Device(Error<E>),
+    /// The receiver was still busy or did not acknowledge the byte after the
+    /// maximum number of polls
+    Timeout,
+}
+
+impl<E> From<Error<E>> for ParallelError<E> {
+    fn from(error: Error<E>) -> Self {
+        ParallelError::Device(error)
+    }
+}
+
+/// Control line assignment. The data lines D0-D7 are always on P0-P7.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pins {
+    /// Strobe output, active low
+    pub strobe: PinFlag,
+    /// Busy input, active high. `None` if not connected.
+    pub busy: Option<PinFlag>,
+    /// Acknowledge input, active low. `None` if not connected.
+    pub ack: Option<PinFlag>,
+}
+
+impl Default for Pins {
+    /// STROBE on P10, BUSY on P11 and no ACK.
+    fn default() -> Self {
+        Pins {
+            strobe: PinFlag::P10,
+            busy: Some(PinFlag::P11),
+            ack: None,
+        }
+    }
+}
+
+/// Parallel data bus over a PCF8575 device
+#[derive(Debug)]
+pub struct ParallelBus<P> {
+    port: P,
+    pins: Pins,
+    max_polls: Option<u32>,
+}
+
+impl<P: WidePort> ParallelBus<P> {
+    /// Create a new parallel bus. No I²C communication is done.
+    pub fn new(port: P, pins: Pins) -> Self {
+        ParallelBus {
+            port,
+            pins,
+            max_polls: Some(DEFAULT_MAX_POLLS),
+        }
+    }
+
+    /// Destroy the bus, return the device.
+    pub fn destroy(self) -> P {
+        self.port
+    }
+
+    /// Set the maximum number of BUSY or ACK polls per byte, or `None` to wait forever.
+    pub fn set_max_polls(&mut self, polls: Option<u32>) {
+        self.max_polls = polls;
+    }
+
+    /// Write all bytes, waiting for the receiver as configured by the
+    /// handshake lines.
+    pub fn write(&mut self, data: &[u8]) -> Result<(), ParallelError<P::BusError>> {
+        self.transmit(data, &mut 0)
+    }
+
+    /// Write the bytes, counting the ones transmitted so far in `written`.
+    fn transmit(
+        &mut self,
+        data: &[u8],
+        written: &mut usize,
+    ) -> Result<(), ParallelError<P::BusError>> {
+        let pins = self.pins;
+        let busy = pins.busy.map_or(0, |pin| pin.mask);
+        let ack = pins.ack.map_or(0, |pin| pin.mask);
+        // keep the handshake lines physically high so that they work as inputs
+        let inputs = (busy | ack) & !self.port.inversion_mask();
+        let base = (self.port.last_set() & !(0xFF | busy | ack)) | pins.strobe.mask | inputs;
+        let mut batch = Batch::new();
+        for (i, byte) in data.iter().enumerate() {
+            if busy != 0 {
+                batch.flush(&mut self.port)?;
+                *written = i;
+                self.wait(busy)?;
+            }
+            let word = base | u16::from(*byte);
+            batch.push(&mut self.port, word)?;
+            batch.push(&mut self.port, word & !pins.strobe.mask)?;
+            batch.push(&mut self.port, word)?;
+            if ack != 0 {
+                batch.flush(&mut self.port)?;
+                self.wait(ack)?;
+                *written = i + 1;
+            }
+        }
+        batch.flush(&mut self.port)?;
+        *written = data.len();
+        Ok(())
+    }
+
+    /// Poll the selected input until it is physically low.
+    fn wait(&mut self, mask: u16) -> Result<(), ParallelError<P::BusError>> {
+        let invert = self.port.inversion_mask();
+        let mut polls = 0;
+        while (self.port.get(PinFlag { mask })? ^ invert) & mask != 0 {
+            polls += 1;
+            if self.max_polls.is_some_and(|max| polls >= max) {
+                return Err(ParallelError::Timeout);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "io")]
+mod io {
+    use super::{ParallelBus, ParallelError};
+    use crate::port::WidePort;
+    use embedded_io::ErrorKind;
+
+    impl<P: WidePort> embedded_io::ErrorType for ParallelBus<P>
+    where
+        P::BusError: core::fmt::Debug,
+    {
+        type Error = ParallelError<P::BusError>;
+    }
+
+    impl<P: WidePort> embedded_io::Write for ParallelBus<P>
+    where
+        P::BusError: core::fmt::Debug,
+    {
+        /// Write the bytes. If an error occurs after some bytes have been
+        /// transmitted, their number is returned.
+        fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+            let mut written = 0;
+            match self.transmit(buf, &mut written) {
+                Ok(()) => Ok(written),
+                Err(_) if written > 0 => Ok(written),
+                Err(error) => Err(error),
+            }
+        }
+
+        fn flush(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    impl<E: core::fmt::Debug> embedded_io::Error for ParallelError<E> {
+        fn kind(&self) -> ErrorKind {
+            match self {
+                ParallelError::Device(_) => ErrorKind::Other,
+                ParallelError::Timeout => ErrorKind::TimedOut,
+            }
+        }
+    }
+}
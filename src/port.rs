@@ -0,0 +1,256 @@
+//! Whole-port access common to all devices
+
+use crate::{Error, Pcf8574, Pcf8574a, Pcf8575, PinFlag};
+use embedded_hal::i2c::I2c;
+
+/// Access to the whole I/O port of a device.
+///
+/// This is implemented for `Pcf8574`, `Pcf8574a` and `Pcf8575` as well as for mutable
+/// references to them, so that the higher-level modules of this crate can work with
+/// any of the devices, either owning it or borrowing it.
+///
+/// Port values are always `u16`. On the 8-bit devices only the lower byte is used
+/// and setting any of the upper bits returns `Error::InvalidInputData`.
+pub trait Port {
+    /// I²C bus error
+    type BusError;
+
+    /// Number of bytes per port word in the `write_array()` and `read_array()` data
+    /// (1 for PCF8574 and PCF8574A, 2 for PCF8575)
+    const WORD_SIZE: usize;
+
+    /// Set the status of all I/O pins.
+    fn set(&mut self, bits: u16) -> Result<(), Error<Self::BusError>>;
+
+    /// Get the status of the selected I/O pins.
+    fn get(&mut self, mask: PinFlag) -> Result<u16, Error<Self::BusError>>;
+
+    /// Status last set to all I/O pins, as passed to `set()`.
+    /// No I²C communication is done.
+    fn last_set(&mut self) -> u16;
+
+    /// Polarity inversion mask of the device. See the device `set_inversion_mask()`.
+    /// No I²C communication is done.
+    ///
+    /// The modules of this crate use it to release the pins they read (set them
+    /// physically high) and to read their physical level, so the inversion does not
+    /// apply to those pins.
+    fn inversion_mask(&mut self) -> u16;
+
+    /// Output pins verified after each write. See the device `set_verify()`.
+    /// No I²C communication is done.
+    fn verify_mask(&mut self) -> u16;
+
+    /// Set the status of all I/O pins repeatedly by looping through each array element.
+    /// See the device `write_array()` method for the data layout.
+    fn write_array(&mut self, data: &[u8]) -> Result<(), Error<Self::BusError>>;
+
+    /// Get the status of the selected I/O pins repeatedly and put them in the
+    /// provided array. See the device `read_array()` method for the data layout.
+    fn read_array(&mut self, mask: PinFlag, data: &mut [u8]) -> Result<(), Error<Self::BusError>>;
+}
+
+impl<T: Port + ?Sized> Port for &mut T {
+    type BusError = T::BusError;
+    const WORD_SIZE: usize = T::WORD_SIZE;
+
+    fn set(&mut self, bits: u16) -> Result<(), Error<Self::BusError>> {
+        T::set(self, bits)
+    }
+
+    fn get(&mut self, mask: PinFlag) -> Result<u16, Error<Self::BusError>> {
+        T::get(self, mask)
+    }
+
+    fn last_set(&mut self) -> u16 {
+        T::last_set(self)
+    }
+
+    fn inversion_mask(&mut self) -> u16 {
+        T::inversion_mask(self)
+    }
+
+    fn verify_mask(&mut self) -> u16 {
+        T::verify_mask(self)
+    }
+
+    fn write_array(&mut self, data: &[u8]) -> Result<(), Error<Self::BusError>> {
+        T::write_array(self, data)
+    }
+
+    fn read_array(&mut self, mask: PinFlag, data: &mut [u8]) -> Result<(), Error<Self::BusError>> {
+        T::read_array(self, mask, data)
+    }
+}
+
+macro_rules! pcf8574_port_impl {
+    ( $( $device_name:ident ),+ ) => {
+        $(
+            impl<I2C, E> Port for $device_name<I2C>
+            where
+                I2C: I2c<Error = E>,
+            {
+                type BusError = E;
+                const WORD_SIZE: usize = 1;
+
+                fn set(&mut self, bits: u16) -> Result<(), Error<E>> {
+                    if (bits >> 8) != 0 {
+                        return Err(Error::InvalidInputData);
+                    }
+                    $device_name::set(self, bits as u8)
+                }
+
+                fn get(&mut self, mask: PinFlag) -> Result<u16, Error<E>> {
+                    $device_name::get(self, mask).map(u16::from)
+                }
+
+                fn last_set(&mut self) -> u16 {
+                    let dev = self.data.get_mut();
+                    u16::from(dev.last_set_mask ^ dev.inversion_mask)
+                }
+
+                fn inversion_mask(&mut self) -> u16 {
+                    u16::from(self.data.get_mut().inversion_mask)
+                }
+
+                fn verify_mask(&mut self) -> u16 {
+                    u16::from(self.data.get_mut().verify_mask)
+                }
+
+                fn write_array(&mut self, data: &[u8]) -> Result<(), Error<E>> {
+                    $device_name::write_array(self, data)
+                }
+
+                fn read_array(&mut self, mask: PinFlag, data: &mut [u8]) -> Result<(), Error<E>> {
+                    $device_name::read_array(self, mask, data)
+                }
+            }
+        )*
+    }
+}
+
+pcf8574_port_impl!(Pcf8574, Pcf8574a);
+
+impl<I2C, E> Port for Pcf8575<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    type BusError = E;
+    const WORD_SIZE: usize = 2;
+
+    fn set(&mut self, bits: u16) -> Result<(), Error<E>> {
+        Pcf8575::set(self, bits)
+    }
+
+    fn get(&mut self, mask: PinFlag) -> Result<u16, Error<E>> {
+        Pcf8575::get(self, mask)
+    }
+
+    fn last_set(&mut self) -> u16 {
+        let dev = self.dev.get_mut();
+        dev.last_set_mask ^ dev.inversion_mask
+    }
+
+    fn inversion_mask(&mut self) -> u16 {
+        self.dev.get_mut().inversion_mask
+    }
+
+    fn verify_mask(&mut self) -> u16 {
+        self.dev.get_mut().verify_mask
+    }
+
+    fn write_array(&mut self, data: &[u8]) -> Result<(), Error<E>> {
+        Pcf8575::write_array(self, data)
+    }
+
+    fn read_array(&mut self, mask: PinFlag, data: &mut [u8]) -> Result<(), Error<E>> {
+        Pcf8575::read_array(self, mask, data)
+    }
+}
+
+/// Port of a 16-bit device.
+///
+/// This is implemented for `Pcf8575` and mutable references to it. The modules using
+/// pins of both bytes of the port require it, so that they cannot be built on an
+/// 8-bit device. This trait is sealed and cannot be implemented outside of this crate.
+///
+/// ```compile_fail
+/// fn wide<P: pcf857x::WidePort>() {}
+/// wide::<pcf857x::Pcf8574<linux_embedded_hal::I2cdev>>();
+/// ```
+pub trait WidePort: Port + private::Sealed {}
+
+impl<I2C: I2c> WidePort for Pcf8575<I2C> {}
+impl<T: WidePort + ?Sized> WidePort for &mut T {}
+
+mod private {
+    use crate::Pcf8575;
+    use embedded_hal::i2c::I2c;
+
+    pub trait Sealed {}
+
+    impl<I2C: I2c> Sealed for Pcf8575<I2C> {}
+    impl<T: Sealed + ?Sized> Sealed for &mut T {}
+}
+
+/// Store a port word in the `write_array()` data layout of the device.
+/// `data` must be at least `P::WORD_SIZE` long.
+pub(crate) fn encode_word<P: Port + ?Sized>(word: u16, data: &mut [u8]) {
+    data[0] = word as u8;
+    if P::WORD_SIZE == 2 {
+        data[1] = (word >> 8) as u8;
+    }
+}
+
+/// Get a port word from the `read_array()` data layout of the device.
+/// `data` must be at least `P::WORD_SIZE` long.
+pub(crate) fn decode_word<P: Port + ?Sized>(data: &[u8]) -> u16 {
+    if P::WORD_SIZE == 2 {
+        u16::from(data[0]) | (u16::from(data[1]) << 8)
+    } else {
+        u16::from(data[0])
+    }
+}
+
+/// Port words waiting to be written with `write_array()`
+pub(crate) struct Batch {
+    buffer: [u8; 64],
+    len: usize,
+    /// Last word pushed
+    pub(crate) last: Option<u16>,
+}
+
+impl Batch {
+    pub(crate) fn new() -> Self {
+        Batch {
+            buffer: [0; 64],
+            len: 0,
+            last: None,
+        }
+    }
+
+    pub(crate) fn push<P: Port>(
+        &mut self,
+        port: &mut P,
+        word: u16,
+    ) -> Result<(), Error<P::BusError>> {
+        encode_word::<P>(word, &mut self.buffer[self.len..]);
+        self.len += P::WORD_SIZE;
+        self.last = Some(word);
+        if self.len == self.buffer.len() {
+            self.flush(port)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn flush<P: Port>(&mut self, port: &mut P) -> Result<(), Error<P::BusError>> {
+        let len = self.len;
+        self.len = 0;
+        port.write_array(&self.buffer[..len])
+    }
+}
+
+/// Iterate over the pin numbers (bit positions) set in a mask.
+pub(crate) fn pins(mask: u16) -> impl Iterator<Item = usize> {
+    (0..16).filter(move |i| mask & (1 << i) != 0)
+}
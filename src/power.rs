@@ -0,0 +1,188 @@
+//! Power sequencing
+//!
+//! A `PowerSequencer` switches on voltage rails whose enable lines are connected to pins
+//! of the device, in the given order. After switching on the rail of a step, it waits
+//! for the settling time of the step and, if the step has a power-good input, checks
+//! that the input goes high within the timeout of the step.
+//!
+//! If a step fails, the rails switched on so far (including the one of the failed step)
+//! are switched off again in reverse order and an error naming the failed step is
+//! returned. Each step has an identifier (e.g. a variant of an application enum).
+//!
+//! Enable lines are active-high and power-good inputs are high when the rail is good.
+//! Use `set_inversion_mask()` on the device for active-low lines.
+//! The pins not used by the sequencer keep their status.
+//!
+//! ```no_run
+//! use linux_embedded_hal::{Delay, I2cdev};
+//! use pcf857x::{power::{PowerSequencer, Step}, Pcf8574, PinFlag, SlaveAddr};
+//!
+//! #[derive(Debug, Clone, Copy, PartialEq)]
+//! enum Rail {
+//!     Core,
+//!     Io,
+//! }
+//!
+//! let dev = I2cdev::new("/dev/i2c-1").unwrap();
+//! let expander = Pcf8574::new(dev, SlaveAddr::default());
+//! let steps = [
+//!     Step { power_good: Some(PinFlag::P4), timeout_us: 5000, ..Step::new(Rail::Core, PinFlag::P0, 1000) },
+//!     Step::new(Rail::Io, PinFlag::P1, 500),
+//! ];
+//! let mut sequencer = PowerSequencer::new(expander, steps);
+//! match sequencer.power_up(&mut Delay) {
+//!     Ok(()) => println!("Powered up"),
+//!     Err(error) => println!("Step {:?} failed", error.step()),
+//! }
+//! ```
+
+use crate::{Error, PinFlag, Port};
+use embedded_hal::delay::DelayNs;
+
+/// Default time between power-good input reads, in microseconds
+pub const DEFAULT_POLL_INTERVAL_US: u32 = 100;
+
+/// Power sequencing errors
+#[derive(Debug)]
+pub enum SequenceError<C, E> {
+    /// Device error while running the step
+    Device(C, Error<E>),
+    /// The power-good input of the step did not go high within the timeout
+    PowerGoodTimeout(C),
+}
+
+impl<C, E> SequenceError<C, E> {
+    /// Identifier of the failed step
+    pub fn step(&self) -> &C {
+        match self {
+            SequenceError::Device(step, _) | SequenceError::PowerGoodTimeout(step) => step,
+        }
+    }
+}
+
+/// Power sequencing step
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Step<C> {
+    /// Step identifier
+    pub id: C,
+    /// Rail enable pin
+    pub rail: PinFlag,
+    /// Time to wait after switching the rail on or off, in microseconds
+    pub settle_us: u32,
+    /// Power-good input checked after the settling time
+    pub power_good: Option<PinFlag>,
+    /// Time the power-good input may take to go high after the settling time,
+    /// in microseconds
+    pub timeout_us: u32,
+}
+
+impl<C> Step<C> {
+    /// Create a step without power-good input.
+    pub fn new(id: C, rail: PinFlag, settle_us: u32) -> Self {
+        Step {
+            id,
+            rail,
+            settle_us,
+            power_good: None,
+            timeout_us: 0,
+        }
+    }
+}
+
+/// Power sequencer with `N` steps
+#[derive(Debug)]
+pub struct PowerSequencer<P, C, const N: usize> {
+    port: P,
+    steps: [Step<C>; N],
+    poll_interval_us: u32,
+    enabled: usize,
+}
+
+impl<P, C, const N: usize> PowerSequencer<P, C, N>
+where
+    P: Port,
+    C: Copy,
+{
+    /// Create a new power sequencer. The rails are assumed to be off.
+    /// No I²C communication is done.
+    pub fn new(port: P, steps: [Step<C>; N]) -> Self {
+        PowerSequencer {
+            port,
+            steps,
+            poll_interval_us: DEFAULT_POLL_INTERVAL_US,
+            enabled: 0,
+        }
+    }
+
+    /// Destroy the sequencer, return the device. The rails keep their status.
+    pub fn destroy(self) -> P {
+        self.port
+    }
+
+    /// Set the time between power-good input reads, in microseconds (at least 1).
+    pub fn set_poll_interval(&mut self, interval_us: u32) {
+        self.poll_interval_us = interval_us.max(1);
+    }
+
+    /// Whether the rails of all steps are on
+    pub fn is_powered_up(&self) -> bool {
+        self.enabled == N
+    }
+
+    /// Run the steps whose rails are not on yet, in order.
+    ///
+    /// If a step fails, the rails that are on are switched off in reverse order on a
+    /// best-effort basis and the error of the failed step is returned.
+    pub fn power_up<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<(), SequenceError<C, P::BusError>> {
+        while self.enabled < N {
+            let step = self.steps[self.enabled];
+            if let Err(error) = self.run(&step, delay) {
+                // the rail of the failed step may be on
+                self.enabled += 1;
+                // best effort, the original error is returned
+                let _ = self.power_down(delay);
+                return Err(error);
+            }
+            self.enabled += 1;
+        }
+        Ok(())
+    }
+
+    /// Switch off the rails that are on in reverse order, waiting for the settling
+    /// time of each step.
+    pub fn power_down<D: DelayNs>(&mut self, delay: &mut D) -> Result<(), Error<P::BusError>> {
+        while self.enabled > 0 {
+            let step = self.steps[self.enabled - 1];
+            let bits = self.port.last_set() & !step.rail.mask;
+            self.port.set(bits)?;
+            delay.delay_us(step.settle_us);
+            self.enabled -= 1;
+        }
+        Ok(())
+    }
+
+    fn run<D: DelayNs>(
+        &mut self,
+        step: &Step<C>,
+        delay: &mut D,
+    ) -> Result<(), SequenceError<C, P::BusError>> {
+        let device_error = |error| SequenceError::Device(step.id, error);
+        let bits = self.port.last_set() | step.rail.mask;
+        self.port.set(bits).map_err(device_error)?;
+        delay.delay_us(step.settle_us);
+        if let Some(power_good) = step.power_good {
+            let mut elapsed = 0;
+            while self.port.get(power_good).map_err(device_error)? & power_good.mask == 0 {
+                if elapsed >= step.timeout_us {
+                    return Err(SequenceError::PowerGoodTimeout(step.id));
+                }
+                delay.delay_us(self.poll_interval_us);
+                elapsed = elapsed.saturating_add(self.poll_interval_us);
+            }
+        }
+        Ok(())
+    }
+}
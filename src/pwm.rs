@@ -0,0 +1,213 @@
+//! Software PWM
+//!
+//! Dims LEDs connected to output pins of the device without extra hardware. Each
+//! PWM period is divided into a number of time slots (the resolution). Each channel
+//! is on during a number of slots proportional to its duty cycle.
+//!
+//! There are two ways of driving the outputs:
+//! - Calling `tick()` periodically. Each call computes the port word for the next
+//!   time slot and writes it with a single `set()` if it changed.
+//! - Calling `write_period()` continuously. This writes the port words of a whole
+//!   period with `write_array()`, so the time slot length is given by the I²C speed.
+//!
+//! Duty cycles go from 0 (off) to 255 (fully on). Optionally, a gamma table can be
+//! applied to them so that the perceived brightness is linear. See `GAMMA`.
+//!
+//! The pins not used by the PWM channels keep their status.
+//!
+//! Each channel implements the `SetDutyCycle` trait from `embedded-hal`. See `channel()`.
+//!
+//! ```no_run
+//! use linux_embedded_hal::I2cdev;
+//! use pcf857x::{pwm::{SoftPwm, GAMMA}, Pcf8575, PinFlag, SlaveAddr};
+//!
+//! let dev = I2cdev::new("/dev/i2c-1").unwrap();
+//! let expander = Pcf8575::new(dev, SlaveAddr::default());
+//! let mut leds = SoftPwm::new(expander, [PinFlag::P0, PinFlag::P1, PinFlag::P2]);
+//! leds.set_gamma(Some(&GAMMA));
+//! leds.set_duty(0, 255).unwrap();
+//! leds.set_duty(1, 128).unwrap();
+//! leds.set_duty(2, 16).unwrap();
+//! loop {
+//!     leds.write_period().unwrap();
+//! }
+//! ```
+
+use crate::port::{encode_word, Port};
+use crate::{Error, PinFlag};
+use embedded_hal::pwm::{ErrorType, SetDutyCycle};
+
+/// Default number of time slots per period
+pub const DEFAULT_RESOLUTION: u16 = 32;
+
+/// Maximum duty cycle
+pub const MAX_DUTY: u8 = 255;
+
+/// Gamma correction table (gamma 2.2) mapping a linear brightness to a duty cycle
+pub const GAMMA: [u8; 256] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2,
+    3, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 6, 6, 6, 6, 7, 7, 7, 8, 8, 8, 9, 9, 9, 10, 10, 11, 11,
+    11, 12, 12, 13, 13, 13, 14, 14, 15, 15, 16, 16, 17, 17, 18, 18, 19, 19, 20, 20, 21, 22, 22, 23,
+    23, 24, 25, 25, 26, 26, 27, 28, 28, 29, 30, 30, 31, 32, 33, 33, 34, 35, 35, 36, 37, 38, 39, 39,
+    40, 41, 42, 43, 43, 44, 45, 46, 47, 48, 49, 49, 50, 51, 52, 53, 54, 55, 56, 57, 58, 59, 60, 61,
+    62, 63, 64, 65, 66, 67, 68, 69, 70, 71, 73, 74, 75, 76, 77, 78, 79, 81, 82, 83, 84, 85, 87, 88,
+    89, 90, 91, 93, 94, 95, 97, 98, 99, 100, 102, 103, 105, 106, 107, 109, 110, 111, 113, 114, 116,
+    117, 119, 120, 121, 123, 124, 126, 127, 129, 130, 132, 133, 135, 137, 138, 140, 141, 143, 145,
+    146, 148, 149, 151, 153, 154, 156, 158, 159, 161, 163, 165, 166, 168, 170, 172, 173, 175, 177,
+    179, 181, 182, 184, 186, 188, 190, 192, 194, 196, 197, 199, 201, 203, 205, 207, 209, 211, 213,
+    215, 217, 219, 221, 223, 225, 227, 229, 231, 234, 236, 238, 240, 242, 244, 246, 248, 251, 253,
+    255,
+];
+
+/// Software PWM on `N` output pins
+#[derive(Debug)]
+pub struct SoftPwm<P, const N: usize> {
+    port: P,
+    pins: [u16; N],
+    duty: [u8; N],
+    gamma: Option<&'static [u8; 256]>,
+    resolution: u16,
+    slot: u16,
+}
+
+impl<P: Port, const N: usize> SoftPwm<P, N> {
+    /// Create a new software PWM with the pins of each channel.
+    ///
+    /// All channels start off, without gamma correction and with the default resolution.
+    /// No I²C communication is done.
+    pub fn new(port: P, pins: [PinFlag; N]) -> Self {
+        SoftPwm {
+            port,
+            pins: pins.map(|pin| pin.mask),
+            duty: [0; N],
+            gamma: None,
+            resolution: DEFAULT_RESOLUTION,
+            slot: 0,
+        }
+    }
+
+    /// Destroy the software PWM instance, return the device.
+    pub fn destroy(self) -> P {
+        self.port
+    }
+
+    /// Set the number of time slots per period (1 to 256).
+    ///
+    /// Higher resolutions give finer dimming steps but a lower PWM frequency.
+    /// Returns `Error::InvalidInputData` if the resolution is out of range.
+    pub fn set_resolution(&mut self, resolution: u16) -> Result<(), Error<P::BusError>> {
+        if resolution == 0 || resolution > 256 {
+            return Err(Error::InvalidInputData);
+        }
+        self.resolution = resolution;
+        self.slot = 0;
+        Ok(())
+    }
+
+    /// Set the gamma table applied to the duty cycles, or `None` for linear output.
+    pub fn set_gamma(&mut self, gamma: Option<&'static [u8; 256]>) {
+        self.gamma = gamma;
+    }
+
+    /// Set the duty cycle of a channel (0 to `MAX_DUTY`).
+    ///
+    /// Returns `Error::InvalidInputData` if the channel does not exist.
+    pub fn set_duty(&mut self, channel: usize, duty: u8) -> Result<(), Error<P::BusError>> {
+        *self.duty.get_mut(channel).ok_or(Error::InvalidInputData)? = duty;
+        Ok(())
+    }
+
+    /// Duty cycle of a channel, as set before gamma correction
+    pub fn duty(&self, channel: usize) -> Result<u8, Error<P::BusError>> {
+        self.duty
+            .get(channel)
+            .copied()
+            .ok_or(Error::InvalidInputData)
+    }
+
+    /// Get a channel, which implements the `SetDutyCycle` trait.
+    ///
+    /// Returns `Error::InvalidInputData` if the channel does not exist.
+    pub fn channel(&mut self, channel: usize) -> Result<Channel<'_, P, N>, Error<P::BusError>> {
+        if channel >= N {
+            return Err(Error::InvalidInputData);
+        }
+        Ok(Channel { pwm: self, channel })
+    }
+
+    /// Output the next time slot. This does a single `set()` if any output changes.
+    pub fn tick(&mut self) -> Result<(), Error<P::BusError>> {
+        let word = self.word(self.slot);
+        self.slot = (self.slot + 1) % self.resolution;
+        if word != self.port.last_set() {
+            self.port.set(word)?;
+        }
+        Ok(())
+    }
+
+    /// Output a whole period with `write_array()`.
+    ///
+    /// Each time slot lasts as long as the transmission of a port word.
+    /// Afterwards, the outputs stay as in the last time slot.
+    pub fn write_period(&mut self) -> Result<(), Error<P::BusError>> {
+        let mut buffer = [0; 64];
+        let mut len = 0;
+        for slot in 0..self.resolution {
+            let word = self.word(slot);
+            encode_word::<P>(word, &mut buffer[len..]);
+            len += P::WORD_SIZE;
+            if len == buffer.len() {
+                self.port.write_array(&buffer)?;
+                len = 0;
+            }
+        }
+        self.slot = 0;
+        self.port.write_array(&buffer[..len])
+    }
+
+    fn on_slots(&self, channel: usize) -> u16 {
+        let duty = self.duty[channel];
+        let duty = self.gamma.map_or(duty, |gamma| gamma[usize::from(duty)]);
+        ((u32::from(duty) * u32::from(self.resolution) + 127) / 255) as u16
+    }
+
+    fn word(&mut self, slot: u16) -> u16 {
+        let mask = self.pins.iter().fold(0, |mask, pin| mask | pin);
+        let on = (0..N)
+            .filter(|channel| slot < self.on_slots(*channel))
+            .fold(0, |word, channel| word | self.pins[channel]);
+        (self.port.last_set() & !mask) | on
+    }
+}
+
+/// Software PWM channel
+#[derive(Debug)]
+pub struct Channel<'a, P, const N: usize> {
+    pwm: &'a mut SoftPwm<P, N>,
+    channel: usize,
+}
+
+impl<'a, P, const N: usize> ErrorType for Channel<'a, P, N>
+where
+    P: Port,
+    P::BusError: core::fmt::Debug,
+{
+    type Error = Error<P::BusError>;
+}
+
+impl<'a, P, const N: usize> SetDutyCycle for Channel<'a, P, N>
+where
+    P: Port,
+    P::BusError: core::fmt::Debug,
+{
+    fn max_duty_cycle(&self) -> u16 {
+        u16::from(MAX_DUTY)
+    }
+
+    fn set_duty_cycle(&mut self, duty: u16) -> Result<(), Self::Error> {
+        if duty > u16::from(MAX_DUTY) {
+            return Err(Error::InvalidInputData);
+        }
+        self.pwm.set_duty(self.channel, duty as u8)
+    }
+}
@@ -0,0 +1,251 @@
+//! Relay banks
+//!
+//! A `RelayBank` drives a group of relays connected to pins of the device. Each channel
+//! has an identifier (e.g. a variant of an application enum) and can be active-low,
+//! as is common on relay boards.
+//!
+//! Safety features:
+//! - Interlock groups: at most one channel of each group can be on at a time
+//!   (e.g. motor forward/reverse). Switching on a channel while another one of its
+//!   group is on fails with `RelayError::Interlock`.
+//! - Minimum on and off times per channel. Switching a channel earlier fails with
+//!   `RelayError::MinimumTime`.
+//! - Fail-safe pattern: the state each channel takes on construction, when the bank
+//!   is dropped, and after any I²C error (on a best-effort basis).
+//!
+//! All channels are updated with a single `set()`. The pins not used by the bank keep
+//! their status. Time is provided by the caller as a free-running `u32` timestamp or
+//! tick count (e.g. milliseconds). Wrap-around is handled.
+//!
+//! Since the fail-safe pattern is written when the bank is dropped, the device cannot
+//! be retrieved from it. Pass a mutable reference to the device to keep using it
+//! afterwards.
+//!
+//! ```no_run
+//! use linux_embedded_hal::I2cdev;
+//! use pcf857x::{relay::{Channel, RelayBank}, Pcf8574, PinFlag, SlaveAddr};
+//!
+//! #[derive(Debug, Clone, Copy, PartialEq)]
+//! enum Relay {
+//!     Forward,
+//!     Reverse,
+//!     Pump,
+//! }
+//!
+//! let dev = I2cdev::new("/dev/i2c-1").unwrap();
+//! let mut expander = Pcf8574::new(dev, SlaveAddr::default());
+//! let channels = [
+//!     Channel { interlock_group: Some(0), active_low: true, ..Channel::new(Relay::Forward, PinFlag::P0) },
+//!     Channel { interlock_group: Some(0), active_low: true, ..Channel::new(Relay::Reverse, PinFlag::P1) },
+//!     Channel { min_off_time: 5000, ..Channel::new(Relay::Pump, PinFlag::P2) },
+//! ];
+//! # let now = 0;
+//! let mut relays = RelayBank::new(&mut expander, channels, now).unwrap();
+//! relays.switch(Relay::Forward, true, now).unwrap();
+//! // Fails, forward is on:
+//! assert!(relays.switch(Relay::Reverse, true, now).is_err());
+//! ```
+
+use crate::{Error, PinFlag, Port};
+
+/// Relay bank errors
+#[derive(Debug)]
+pub enum RelayError<E> {
+    /// Device error
+    Device(Error<E>),
+    /// Unknown channel identifier
+    UnknownChannel,
+    /// Another channel of the same interlock group is on
+    Interlock,
+    /// The minimum on or off time of the channel has not elapsed yet
+    MinimumTime,
+}
+
+impl<E> From<Error<E>> for RelayError<E> {
+    fn from(error: Error<E>) -> Self {
+        RelayError::Device(error)
+    }
+}
+
+/// Relay channel configuration
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Channel<C> {
+    /// Channel identifier
+    pub id: C,
+    /// Pin driving the relay
+    pub pin: PinFlag,
+    /// Whether the relay is on when the pin is low
+    pub active_low: bool,
+    /// Interlock group. At most one channel of each group can be on at a time.
+    pub interlock_group: Option<u8>,
+    /// Minimum time the relay must stay on before switching off
+    pub min_on_time: u32,
+    /// Minimum time the relay must stay off before switching on
+    pub min_off_time: u32,
+    /// State of the relay in the fail-safe pattern
+    pub fail_safe_on: bool,
+}
+
+impl<C> Channel<C> {
+    /// Create a channel configuration: active-high, no interlock group,
+    /// no minimum times and off in the fail-safe pattern.
+    pub fn new(id: C, pin: PinFlag) -> Self {
+        Channel {
+            id,
+            pin,
+            active_low: false,
+            interlock_group: None,
+            min_on_time: 0,
+            min_off_time: 0,
+            fail_safe_on: false,
+        }
+    }
+}
+
+/// Bank of `N` relays
+#[derive(Debug)]
+pub struct RelayBank<P: Port, C, const N: usize> {
+    port: P,
+    channels: [Channel<C>; N],
+    on: [bool; N],
+    changed_at: [u32; N],
+}
+
+impl<P, C, const N: usize> RelayBank<P, C, N>
+where
+    P: Port,
+    C: Copy + PartialEq,
+{
+    /// Create a new relay bank and write the fail-safe pattern.
+    ///
+    /// Returns `RelayError::Interlock` if the fail-safe pattern switches on more
+    /// than one channel of an interlock group.
+    pub fn new(
+        port: P,
+        channels: [Channel<C>; N],
+        now: u32,
+    ) -> Result<Self, RelayError<P::BusError>> {
+        let violation = channels.iter().enumerate().any(|(i, channel)| {
+            channel.fail_safe_on
+                && channel.interlock_group.is_some()
+                && channels[i + 1..].iter().any(|other| {
+                    other.fail_safe_on && other.interlock_group == channel.interlock_group
+                })
+        });
+        if violation {
+            return Err(RelayError::Interlock);
+        }
+        // written before the bank exists, so that a failure is not retried on drop
+        let mut port = port;
+        let word = word(&channels, port.last_set(), |i| channels[i].fail_safe_on);
+        port.set(word)?;
+        Ok(RelayBank {
+            port,
+            on: channels.map(|channel| channel.fail_safe_on),
+            channels,
+            changed_at: [now; N],
+        })
+    }
+
+    /// Switch a relay on or off.
+    ///
+    /// Switching a relay to its current state does nothing.
+    pub fn switch(&mut self, id: C, on: bool, now: u32) -> Result<(), RelayError<P::BusError>> {
+        let index = self
+            .channels
+            .iter()
+            .position(|channel| channel.id == id)
+            .ok_or(RelayError::UnknownChannel)?;
+        if self.on[index] == on {
+            return Ok(());
+        }
+        let channel = &self.channels[index];
+        let min_time = if on {
+            channel.min_off_time
+        } else {
+            channel.min_on_time
+        };
+        if now.wrapping_sub(self.changed_at[index]) < min_time {
+            return Err(RelayError::MinimumTime);
+        }
+        if on && self.interlocked(index) {
+            return Err(RelayError::Interlock);
+        }
+        self.on[index] = on;
+        self.changed_at[index] = now;
+        self.write(now)?;
+        Ok(())
+    }
+
+    /// Whether a relay is on
+    pub fn is_on(&self, id: C) -> Result<bool, RelayError<P::BusError>> {
+        self.channels
+            .iter()
+            .position(|channel| channel.id == id)
+            .map(|index| self.on[index])
+            .ok_or(RelayError::UnknownChannel)
+    }
+
+    /// Write the fail-safe pattern, regardless of the minimum times.
+    pub fn fail_safe(&mut self, now: u32) -> Result<(), Error<P::BusError>> {
+        self.set_fail_safe_state(now);
+        self.write(now)
+    }
+
+    fn interlocked(&self, index: usize) -> bool {
+        let group = self.channels[index].interlock_group;
+        group.is_some()
+            && self
+                .channels
+                .iter()
+                .enumerate()
+                .any(|(i, channel)| i != index && self.on[i] && channel.interlock_group == group)
+    }
+}
+
+impl<P: Port, C, const N: usize> RelayBank<P, C, N> {
+    fn set_fail_safe_state(&mut self, now: u32) {
+        for (i, channel) in self.channels.iter().enumerate() {
+            if self.on[i] != channel.fail_safe_on {
+                self.on[i] = channel.fail_safe_on;
+                self.changed_at[i] = now;
+            }
+        }
+    }
+
+    fn write(&mut self, now: u32) -> Result<(), Error<P::BusError>> {
+        let word = word(&self.channels, self.port.last_set(), |i| self.on[i]);
+        self.port.set(word).inspect_err(|_| {
+            // best effort, the original error is returned
+            self.set_fail_safe_state(now);
+            let _ = self.write_fail_safe();
+        })
+    }
+
+    fn write_fail_safe(&mut self) -> Result<(), Error<P::BusError>> {
+        let channels = &self.channels;
+        let word = word(channels, self.port.last_set(), |i| channels[i].fail_safe_on);
+        self.port.set(word)
+    }
+}
+
+/// Port word with the relay pins driven as given by `on` and the other pins as in
+/// `last_set`.
+fn word<C>(channels: &[Channel<C>], last_set: u16, on: impl Fn(usize) -> bool) -> u16 {
+    let mask = channels
+        .iter()
+        .fold(0, |mask, channel| mask | channel.pin.mask);
+    channels
+        .iter()
+        .enumerate()
+        .filter(|(i, channel)| on(*i) != channel.active_low)
+        .fold(last_set & !mask, |word, (_, channel)| {
+            word | channel.pin.mask
+        })
+}
+
+impl<P: Port, C, const N: usize> Drop for RelayBank<P, C, N> {
+    fn drop(&mut self) {
+        let _ = self.write_fail_safe();
+    }
+}
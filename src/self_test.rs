@@ -0,0 +1,124 @@
+//! Loopback self-test
+//!
+//! For end-of-line testing, a fixture connecting pairs of pins of the device is plugged
+//! into the header. `loopback()` checks the connections of the given pin pairs:
+//!
+//! 1. All the pins of the pairs are set high. Pins reading low are stuck low.
+//! 2. Each pin is driven low in turn while the others stay high. Its partner must
+//!    follow. If it does not, the connection is open. Any other pin following it is
+//!    shorted to it.
+//!
+//! Since the outputs of the devices are quasi-bidirectional, a pin set high is only
+//! weakly pulled up and follows a connected pin driven low.
+//! The pins work with physical levels, so the polarity inversion mask of the device does
+//! not apply to them. The output verification mask (see `set_verify()`) must not include
+//! the tested pins, since the partners of a pin driven low read back low.
+//! The pins not included in the pairs are not checked and the status of all the pins
+//! is restored afterwards.
+//!
+//! ```no_run
+//! use linux_embedded_hal::I2cdev;
+//! use pcf857x::{self_test::loopback, Pcf8574, PinFlag, SlaveAddr};
+//!
+//! let dev = I2cdev::new("/dev/i2c-1").unwrap();
+//! let mut expander = Pcf8574::new(dev, SlaveAddr::default());
+//! let pairs = [
+//!     (PinFlag::P0, PinFlag::P1),
+//!     (PinFlag::P2, PinFlag::P3),
+//! ];
+//! let report = loopback(&mut expander, &pairs).unwrap();
+//! if !report.passed() {
+//!     println!("Open: {:#b}", report.open);
+//!     println!("Shorted to P0: {:#b}", report.shorts(PinFlag::P0));
+//! }
+//! ```
+
+use crate::port::pins;
+use crate::{Error, PinFlag, Port};
+
+/// Loopback self-test report.
+///
+/// Pins are given as masks with the same layout as the values returned by `get()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoopbackReport {
+    /// Pins whose partner did not follow when driven low
+    pub open: u16,
+    /// Pins reading low while no pin was driven low
+    pub stuck_low: u16,
+    shorts: [u16; 16],
+}
+
+impl LoopbackReport {
+    /// Whether no open, stuck or shorted pins were found
+    pub fn passed(&self) -> bool {
+        self.open == 0 && self.stuck_low == 0 && self.shorts.iter().all(|shorts| *shorts == 0)
+    }
+
+    /// Pins that followed any of the given pins when driven low, although not
+    /// connected to them
+    pub fn shorts(&self, pin: PinFlag) -> u16 {
+        pins(pin.mask).fold(0, |shorts, i| shorts | self.shorts[i])
+    }
+}
+
+/// Run the loopback self-test on the given pairs of connected pins.
+///
+/// Returns `Error::InvalidInputData` if a pin of the pairs is not a single pin,
+/// a pin is paired with itself or a pin is included in the output verification mask.
+pub fn loopback<P: Port>(
+    mut port: P,
+    pairs: &[(PinFlag, PinFlag)],
+) -> Result<LoopbackReport, Error<P::BusError>> {
+    let single = |pin: PinFlag| pin.mask.count_ones() == 1;
+    let verified = port.verify_mask();
+    if pairs.iter().any(|(a, b)| {
+        !single(*a) || !single(*b) || a.mask == b.mask || (a.mask | b.mask) & verified != 0
+    }) {
+        return Err(Error::InvalidInputData);
+    }
+    let previous = port.last_set();
+    let result = run(&mut port, pairs, previous);
+    // the original error is returned if the test failed
+    let restored = port.set(previous);
+    let report = result?;
+    restored?;
+    Ok(report)
+}
+
+fn run<P: Port>(
+    port: &mut P,
+    pairs: &[(PinFlag, PinFlag)],
+    previous: u16,
+) -> Result<LoopbackReport, Error<P::BusError>> {
+    let mask = pairs.iter().fold(0, |mask, (a, b)| mask | a.mask | b.mask);
+    let invert = port.inversion_mask() & mask;
+    let all_high = (previous & !mask) | (mask ^ invert);
+    port.set(all_high)?;
+    let stuck_low = mask & !(port.get(PinFlag { mask })? ^ invert);
+    let mut report = LoopbackReport {
+        open: 0,
+        stuck_low,
+        shorts: [0; 16],
+    };
+    for i in pins(mask) {
+        let driven = 1 << i;
+        let partners = pairs.iter().fold(0, |partners, (a, b)| {
+            if a.mask == driven {
+                partners | b.mask
+            } else if b.mask == driven {
+                partners | a.mask
+            } else {
+                partners
+            }
+        });
+        port.set(all_high ^ driven)?;
+        // the driven pin must not be released for reading
+        let inputs = mask & !driven;
+        let low = inputs & !(port.get(PinFlag { mask: inputs })? ^ invert);
+        if partners & !low != 0 {
+            report.open |= driven;
+        }
+        report.shorts[i] = low & !partners & !stuck_low;
+    }
+    Ok(report)
+}
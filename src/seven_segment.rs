@@ -0,0 +1,232 @@
+//! Multiplexed seven-segment displays
+//!
+//! The segment lines (A to G and the decimal point) and the digit select lines of a
+//! multiplexed display are connected to pins of the device. A PCF8575 can drive
+//! 8 segment lines plus up to 8 digits.
+//!
+//! The displayed contents are kept in a buffer. The display is refreshed by calling
+//! `tick()` periodically (e.g. every 2-5 ms): each call shows the next digit with a
+//! single `set()`. The pins not used by the display keep their status.
+//!
+//! Segment and digit lines can be active-high or active-low. For example, a
+//! common-anode display has active-low segment lines.
+//!
+//! ```no_run
+//! use linux_embedded_hal::I2cdev;
+//! use pcf857x::{Pcf8575, PinFlag, SlaveAddr};
+//! use pcf857x::seven_segment::{Pins, Polarity, SevenSegment};
+//!
+//! let dev = I2cdev::new("/dev/i2c-1").unwrap();
+//! let expander = Pcf8575::new(dev, SlaveAddr::default());
+//! let pins = Pins {
+//!     segments: [
+//!         PinFlag::P0, PinFlag::P1, PinFlag::P2, PinFlag::P3,
+//!         PinFlag::P4, PinFlag::P5, PinFlag::P6, PinFlag::P7,
+//!     ],
+//!     digits: [PinFlag::P10, PinFlag::P11, PinFlag::P12, PinFlag::P13],
+//!     segment_polarity: Polarity::ActiveLow,
+//!     digit_polarity: Polarity::ActiveHigh,
+//! };
+//! let mut display = SevenSegment::new(expander, pins);
+//! display.write_str("12.3F").unwrap();
+//! loop {
+//!     display.tick().unwrap();
+//!     // wait a few milliseconds
+//! }
+//! ```
+
+use crate::{Error, PinFlag, Port};
+
+/// Segment A bit in a segment pattern
+pub const SEG_A: u8 = 1 << 0;
+/// Segment B bit in a segment pattern
+pub const SEG_B: u8 = 1 << 1;
+/// Segment C bit in a segment pattern
+pub const SEG_C: u8 = 1 << 2;
+/// Segment D bit in a segment pattern
+pub const SEG_D: u8 = 1 << 3;
+/// Segment E bit in a segment pattern
+pub const SEG_E: u8 = 1 << 4;
+/// Segment F bit in a segment pattern
+pub const SEG_F: u8 = 1 << 5;
+/// Segment G bit in a segment pattern
+pub const SEG_G: u8 = 1 << 6;
+/// Decimal point bit in a segment pattern
+pub const SEG_DP: u8 = 1 << 7;
+
+const HEX_DIGITS: [u8; 16] = [
+    0x3F, 0x06, 0x5B, 0x4F, 0x66, 0x6D, 0x7D, 0x07, 0x7F, 0x6F, 0x77, 0x7C, 0x39, 0x5E, 0x79, 0x71,
+];
+
+/// Get the segment pattern for a character.
+///
+/// Supported are hexadecimal digits, the letters that can be represented with
+/// seven segments (in upper or lower case as it fits best), space, `-`, `_`
+/// and `=`. Returns `None` for other characters.
+pub fn font(c: char) -> Option<u8> {
+    if let Some(digit) = c.to_digit(16) {
+        return Some(HEX_DIGITS[digit as usize]);
+    }
+    let pattern = match c {
+        ' ' => 0,
+        '-' => SEG_G,
+        '_' => SEG_D,
+        '=' => SEG_D | SEG_G,
+        'G' | 'g' => 0x3D,
+        'H' => 0x76,
+        'h' => 0x74,
+        'I' => 0x06,
+        'i' => 0x04,
+        'J' | 'j' => 0x1E,
+        'L' | 'l' => 0x38,
+        'N' | 'n' => 0x54,
+        'O' => 0x3F,
+        'o' => 0x5C,
+        'P' | 'p' => 0x73,
+        'Q' | 'q' => 0x67,
+        'R' | 'r' => 0x50,
+        'S' | 's' => 0x6D,
+        'T' | 't' => 0x78,
+        'U' => 0x3E,
+        'u' => 0x1C,
+        'Y' | 'y' => 0x6E,
+        _ => return None,
+    };
+    Some(pattern)
+}
+
+/// Line polarity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Polarity {
+    /// The line is active when high
+    ActiveHigh,
+    /// The line is active when low
+    ActiveLow,
+}
+
+impl Polarity {
+    pub(crate) fn apply(self, active: u16, all: u16) -> u16 {
+        match self {
+            Polarity::ActiveHigh => active,
+            Polarity::ActiveLow => all & !active,
+        }
+    }
+}
+
+/// Pin assignment of a display with `N` digits
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pins<const N: usize> {
+    /// Segment lines A, B, C, D, E, F, G and decimal point
+    pub segments: [PinFlag; 8],
+    /// Digit select lines, leftmost digit first
+    pub digits: [PinFlag; N],
+    /// Segment line polarity
+    pub segment_polarity: Polarity,
+    /// Digit select line polarity
+    pub digit_polarity: Polarity,
+}
+
+/// Multiplexed seven-segment display with `N` digits
+#[derive(Debug)]
+pub struct SevenSegment<P, const N: usize> {
+    port: P,
+    pins: Pins<N>,
+    buffer: [u8; N],
+    current: usize,
+}
+
+impl<P: Port, const N: usize> SevenSegment<P, N> {
+    /// Create a new display. The contents are blank.
+    pub fn new(port: P, pins: Pins<N>) -> Self {
+        SevenSegment {
+            port,
+            pins,
+            buffer: [0; N],
+            current: 0,
+        }
+    }
+
+    /// Destroy the display instance, return the device.
+    pub fn destroy(self) -> P {
+        self.port
+    }
+
+    /// Show text, aligned left. A `.` sets the decimal point of the previous character.
+    ///
+    /// Returns `Error::InvalidInputData` if the text contains unsupported characters
+    /// or does not fit. In that case the contents are not changed.
+    pub fn write_str(&mut self, text: &str) -> Result<(), Error<P::BusError>> {
+        let mut buffer = [0; N];
+        let mut len = 0;
+        for c in text.chars() {
+            if c == '.' && len > 0 && buffer[len - 1] & SEG_DP == 0 {
+                buffer[len - 1] |= SEG_DP;
+                continue;
+            }
+            let pattern = if c == '.' { Some(SEG_DP) } else { font(c) };
+            match (pattern, buffer.get_mut(len)) {
+                (Some(pattern), Some(digit)) => *digit = pattern,
+                _ => return Err(Error::InvalidInputData),
+            }
+            len += 1;
+        }
+        self.buffer = buffer;
+        Ok(())
+    }
+
+    /// Set the segment pattern of a digit (see the `SEG_*` constants).
+    ///
+    /// Returns `Error::InvalidInputData` if the digit does not exist.
+    pub fn set_segments(&mut self, digit: usize, pattern: u8) -> Result<(), Error<P::BusError>> {
+        let segments = self.buffer.get_mut(digit).ok_or(Error::InvalidInputData)?;
+        *segments = pattern;
+        Ok(())
+    }
+
+    /// Segment patterns of all digits
+    pub fn segments(&self) -> &[u8; N] {
+        &self.buffer
+    }
+
+    /// Clear the contents.
+    pub fn clear(&mut self) {
+        self.buffer = [0; N];
+    }
+
+    /// Show the next digit. This does a single `set()`.
+    pub fn tick(&mut self) -> Result<(), Error<P::BusError>> {
+        if N == 0 {
+            return Ok(());
+        }
+        let word = self.word(Some(self.current));
+        self.current = (self.current + 1) % N;
+        self.port.set(word)
+    }
+
+    /// Switch all digits off until the next `tick()`.
+    pub fn blank(&mut self) -> Result<(), Error<P::BusError>> {
+        let word = self.word(None);
+        self.port.set(word)
+    }
+
+    fn word(&mut self, digit: Option<usize>) -> u16 {
+        let segments_mask = self
+            .pins
+            .segments
+            .iter()
+            .fold(0, |mask, pin| mask | pin.mask);
+        let digits_mask = self.pins.digits.iter().fold(0, |mask, pin| mask | pin.mask);
+        let pattern = digit.map_or(0, |digit| self.buffer[digit]);
+        let segments = self
+            .pins
+            .segments
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| pattern & (1 << i) != 0)
+            .fold(0, |mask, (_, pin)| mask | pin.mask);
+        let selected = digit.map_or(0, |digit| self.pins.digits[digit].mask);
+        (self.port.last_set() & !(segments_mask | digits_mask))
+            | self.pins.segment_polarity.apply(segments, segments_mask)
+            | self.pins.digit_polarity.apply(selected, digits_mask)
+    }
+}
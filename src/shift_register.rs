@@ -0,0 +1,180 @@
+//! 74HC595 and 74HC165 shift register chains
+//!
+//! Chains of 74HC595 serial-in/parallel-out and 74HC165 parallel-in/serial-out shift
+//! registers can be connected to a few pins of the device to extend its outputs and
+//! inputs further. The chains are exposed as wide output and input ports with `N`
+//! bytes, where byte 0 corresponds to the register connected to the device and
+//! bit 0 of each byte to the register pin A.
+//!
+//! The whole shift sequence of a 74HC595 chain is generated with `write_array()`.
+//! The 74HC165 chain is loaded and clocked with `write_array()` and each bit
+//! is sampled with `read_array()`. The pins not used by the chains keep their status.
+//!
+//! ```no_run
+//! use linux_embedded_hal::I2cdev;
+//! use pcf857x::shift_register::{Hc165, Hc165Pins, Hc595, Hc595Pins};
+//! use pcf857x::{Pcf8574, PinFlag, SlaveAddr};
+//!
+//! let dev = I2cdev::new("/dev/i2c-1").unwrap();
+//! let mut expander = Pcf8574::new(dev, SlaveAddr::default());
+//! let pins = Hc595Pins {
+//!     data: PinFlag::P0,
+//!     clock: PinFlag::P1,
+//!     latch: PinFlag::P2,
+//! };
+//! let mut outputs: Hc595<_, 2> = Hc595::new(&mut expander, pins);
+//! outputs.write([0b1010_1010, 0xFF]).unwrap();
+//! outputs.set_output(15, false).unwrap();
+//!
+//! let pins = Hc165Pins {
+//!     data: PinFlag::P4,
+//!     clock: PinFlag::P5,
+//!     load: PinFlag::P6,
+//! };
+//! let mut inputs: Hc165<_, 3> = Hc165::new(&mut expander, pins);
+//! let levels = inputs.read().unwrap();
+//! ```
+
+use crate::port::{decode_word, Batch, Port};
+use crate::{Error, PinFlag};
+
+/// Pin assignment of a 74HC595 chain
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hc595Pins {
+    /// Serial data input (SER)
+    pub data: PinFlag,
+    /// Shift register clock (SRCLK)
+    pub clock: PinFlag,
+    /// Storage register clock (RCLK)
+    pub latch: PinFlag,
+}
+
+/// Chain of `N` 74HC595 shift registers
+#[derive(Debug)]
+pub struct Hc595<P, const N: usize> {
+    port: P,
+    pins: Hc595Pins,
+    outputs: [u8; N],
+}
+
+impl<P: Port, const N: usize> Hc595<P, N> {
+    /// Create a new chain. The outputs are assumed to be low.
+    /// No I²C communication is done.
+    pub fn new(port: P, pins: Hc595Pins) -> Self {
+        Hc595 {
+            port,
+            pins,
+            outputs: [0; N],
+        }
+    }
+
+    /// Destroy the chain instance, return the device.
+    pub fn destroy(self) -> P {
+        self.port
+    }
+
+    /// Status last written to the outputs
+    pub fn outputs(&self) -> &[u8; N] {
+        &self.outputs
+    }
+
+    /// Shift the status of all outputs into the chain and latch it.
+    pub fn write(&mut self, outputs: [u8; N]) -> Result<(), Error<P::BusError>> {
+        let pins = self.pins;
+        let base = self.port.last_set() & !(pins.data.mask | pins.clock.mask | pins.latch.mask);
+        let mut batch = Batch::new();
+        // the first bit shifted ends up in the last output of the chain
+        for byte in outputs.iter().rev() {
+            for bit in (0..8).rev() {
+                let data = if byte & (1 << bit) != 0 {
+                    pins.data.mask
+                } else {
+                    0
+                };
+                batch.push(&mut self.port, base | data)?;
+                batch.push(&mut self.port, base | data | pins.clock.mask)?;
+            }
+        }
+        batch.push(&mut self.port, base | pins.latch.mask)?;
+        batch.push(&mut self.port, base)?;
+        batch.flush(&mut self.port)?;
+        self.outputs = outputs;
+        Ok(())
+    }
+
+    /// Set the status of a single output, where outputs 0 to 7 correspond to
+    /// the first register, 8 to 15 to the second one and so on.
+    ///
+    /// The whole chain is written. Returns `Error::InvalidInputData` if the
+    /// output does not exist.
+    pub fn set_output(&mut self, output: usize, high: bool) -> Result<(), Error<P::BusError>> {
+        let mut outputs = self.outputs;
+        let byte = outputs.get_mut(output / 8).ok_or(Error::InvalidInputData)?;
+        if high {
+            *byte |= 1 << (output % 8);
+        } else {
+            *byte &= !(1 << (output % 8));
+        }
+        self.write(outputs)
+    }
+}
+
+/// Pin assignment of a 74HC165 chain
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hc165Pins {
+    /// Serial data output (QH) of the register connected to the device
+    pub data: PinFlag,
+    /// Clock (CLK). Clock inhibit (CLK INH) must be tied low.
+    pub clock: PinFlag,
+    /// Shift/load (SH/LD)
+    pub load: PinFlag,
+}
+
+/// Chain of `N` 74HC165 shift registers
+#[derive(Debug)]
+pub struct Hc165<P, const N: usize> {
+    port: P,
+    pins: Hc165Pins,
+}
+
+impl<P: Port, const N: usize> Hc165<P, N> {
+    /// Create a new chain. No I²C communication is done.
+    pub fn new(port: P, pins: Hc165Pins) -> Self {
+        Hc165 { port, pins }
+    }
+
+    /// Destroy the chain instance, return the device.
+    pub fn destroy(self) -> P {
+        self.port
+    }
+
+    /// Load the status of all inputs and shift it out of the chain.
+    pub fn read(&mut self) -> Result<[u8; N], Error<P::BusError>> {
+        let pins = self.pins;
+        let invert = self.port.inversion_mask();
+        // keep the data pin physically high so that it works as input
+        let base = (self.port.last_set() & !(pins.clock.mask | pins.load.mask | pins.data.mask))
+            | (pins.data.mask & !invert)
+            | pins.load.mask;
+        let mut batch = Batch::new();
+        batch.push(&mut self.port, base & !pins.load.mask)?;
+        batch.push(&mut self.port, base)?;
+        let mut inputs = [0; N];
+        for (i, byte) in inputs.iter_mut().enumerate() {
+            for bit in (0..8).rev() {
+                if i > 0 || bit < 7 {
+                    batch.push(&mut self.port, base | pins.clock.mask)?;
+                    batch.push(&mut self.port, base)?;
+                }
+                batch.flush(&mut self.port)?;
+                let mut sample = [0; 2];
+                let sample = &mut sample[..P::WORD_SIZE];
+                self.port.read_array(pins.data, sample)?;
+                if (decode_word::<P>(sample) ^ invert) & pins.data.mask != 0 {
+                    *byte |= 1 << bit;
+                }
+            }
+        }
+        Ok(inputs)
+    }
+}
@@ -0,0 +1,301 @@
+//! Bit-banged SPI master
+//!
+//! Talks to slow SPI peripherals (e.g. a MAX7219 or an SPI EEPROM) connected to pins
+//! of the device. `BitBangBus` implements the `SpiBus` trait from `embedded-hal` using
+//! the SCK, MOSI and MISO pins. `BitBangDevice` adds a chip select pin on the same
+//! device and implements the `SpiDevice` trait.
+//!
+//! All SPI modes (0 to 3) are supported. Data is transferred most significant bit first.
+//!
+//! Each bit takes two port words. The words for consecutive bits are packed into as
+//! few `write_array()` calls as possible. Whenever data must be read, MISO is sampled
+//! with `read_array()` after each sampling clock edge, so reading is much slower
+//! than writing. The pins not used by the bus keep their status.
+//!
+//! ```no_run
+//! use embedded_hal::spi::{Operation, SpiDevice};
+//! use linux_embedded_hal::{Delay, I2cdev};
+//! use pcf857x::spi::{BitBangBus, BitBangDevice, Pins, MODE_0};
+//! use pcf857x::{Pcf8574, PinFlag, SlaveAddr};
+//!
+//! let dev = I2cdev::new("/dev/i2c-1").unwrap();
+//! let expander = Pcf8574::new(dev, SlaveAddr::default());
+//! let pins = Pins {
+//!     sck: PinFlag::P0,
+//!     mosi: PinFlag::P1,
+//!     miso: PinFlag::P2,
+//! };
+//! let bus = BitBangBus::new(expander, pins, MODE_0);
+//! let mut eeprom = BitBangDevice::new(bus, PinFlag::P3, Delay);
+//! let mut data = [0; 4];
+//! // read 4 bytes from address 0
+//! eeprom
+//!     .transaction(&mut [Operation::Write(&[0x03, 0x00]), Operation::Read(&mut data)])
+//!     .unwrap();
+//! ```
+
+use crate::port::{decode_word, Batch, Port};
+use crate::{Error, PinFlag};
+use embedded_hal::delay::DelayNs;
+use embedded_hal::spi::{ErrorType, Operation, SpiBus, SpiDevice};
+
+pub use embedded_hal::spi::{Mode, Phase, Polarity, MODE_0, MODE_1, MODE_2, MODE_3};
+
+/// Pin assignment of the bus
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pins {
+    /// Clock output
+    pub sck: PinFlag,
+    /// Data output
+    pub mosi: PinFlag,
+    /// Data input
+    pub miso: PinFlag,
+}
+
+/// Bit-banged SPI bus
+#[derive(Debug)]
+pub struct BitBangBus<P> {
+    port: P,
+    pins: Pins,
+    mode: Mode,
+}
+
+impl<P: Port> BitBangBus<P> {
+    /// Create a new bus. No I²C communication is done.
+    ///
+    /// The clock is set to its idle level at the beginning of the first transfer.
+    pub fn new(port: P, pins: Pins, mode: Mode) -> Self {
+        BitBangBus { port, pins, mode }
+    }
+
+    /// Destroy the bus instance, return the device.
+    pub fn destroy(self) -> P {
+        self.port
+    }
+
+    /// Set the SPI mode.
+    pub fn set_mode(&mut self, mode: Mode) {
+        self.mode = mode;
+    }
+
+    /// Port word with the clock idle, MOSI low, MISO physically high (as input) and
+    /// the pins not used by the bus as last set, except for `clear`.
+    fn base(&mut self, clear: u16) -> u16 {
+        let pins = self.pins;
+        let bus = pins.sck.mask | pins.mosi.mask | pins.miso.mask;
+        let miso = pins.miso.mask & !self.port.inversion_mask();
+        (self.port.last_set() & !(bus | clear)) | miso | self.idle()
+    }
+
+    fn idle(&self) -> u16 {
+        match self.mode.polarity {
+            Polarity::IdleLow => 0,
+            Polarity::IdleHigh => self.pins.sck.mask,
+        }
+    }
+
+    /// Shift a byte out and, if `read`, in.
+    fn shift(
+        &mut self,
+        batch: &mut Batch,
+        base: u16,
+        byte: u8,
+        read: bool,
+    ) -> Result<u8, Error<P::BusError>> {
+        let sck = self.pins.sck.mask;
+        // the data is shifted out on the first word and sampled on the second
+        let shift_edge = match self.mode.phase {
+            Phase::CaptureOnFirstTransition => 0,
+            Phase::CaptureOnSecondTransition => sck,
+        };
+        let invert = self.port.inversion_mask();
+        let mut input = 0;
+        for bit in (0..8).rev() {
+            let mosi = if byte & (1 << bit) != 0 {
+                self.pins.mosi.mask
+            } else {
+                0
+            };
+            batch.push(&mut self.port, (base ^ shift_edge) | mosi)?;
+            batch.push(&mut self.port, (base ^ shift_edge ^ sck) | mosi)?;
+            if read {
+                batch.flush(&mut self.port)?;
+                let mut sample = [0; 2];
+                let sample = &mut sample[..P::WORD_SIZE];
+                self.port.read_array(self.pins.miso, sample)?;
+                if (decode_word::<P>(sample) ^ invert) & self.pins.miso.mask != 0 {
+                    input |= 1 << bit;
+                }
+            }
+        }
+        Ok(input)
+    }
+
+    /// Return the clock to its idle level.
+    fn finish(&mut self, batch: &mut Batch, base: u16) -> Result<(), Error<P::BusError>> {
+        let sck = self.pins.sck.mask;
+        if let Some(last) = batch.last {
+            if (last ^ base) & sck != 0 {
+                batch.push(&mut self.port, last ^ sck)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn transfer_bytes(
+        &mut self,
+        batch: &mut Batch,
+        base: u16,
+        read: &mut [u8],
+        write: &[u8],
+    ) -> Result<(), Error<P::BusError>> {
+        for i in 0..read.len().max(write.len()) {
+            let byte = write.get(i).copied().unwrap_or(0);
+            let input = self.shift(batch, base, byte, i < read.len())?;
+            if let Some(word) = read.get_mut(i) {
+                *word = input;
+            }
+        }
+        self.finish(batch, base)
+    }
+
+    fn transfer_bytes_in_place(
+        &mut self,
+        batch: &mut Batch,
+        base: u16,
+        words: &mut [u8],
+    ) -> Result<(), Error<P::BusError>> {
+        for word in words {
+            *word = self.shift(batch, base, *word, true)?;
+        }
+        self.finish(batch, base)
+    }
+
+    fn run(
+        &mut self,
+        f: impl FnOnce(&mut Self, &mut Batch, u16) -> Result<(), Error<P::BusError>>,
+    ) -> Result<(), Error<P::BusError>> {
+        let mut batch = Batch::new();
+        let base = self.base(0);
+        if (self.port.last_set() ^ base) & self.pins.sck.mask != 0 {
+            batch.push(&mut self.port, base)?;
+        }
+        f(self, &mut batch, base)?;
+        batch.flush(&mut self.port)
+    }
+}
+
+impl<P> ErrorType for BitBangBus<P>
+where
+    P: Port,
+    P::BusError: core::fmt::Debug,
+{
+    type Error = Error<P::BusError>;
+}
+
+impl<P> SpiBus for BitBangBus<P>
+where
+    P: Port,
+    P::BusError: core::fmt::Debug,
+{
+    fn read(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+        self.run(|bus, batch, base| bus.transfer_bytes(batch, base, words, &[]))
+    }
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        self.run(|bus, batch, base| bus.transfer_bytes(batch, base, &mut [], words))
+    }
+
+    fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), Self::Error> {
+        self.run(|bus, batch, base| bus.transfer_bytes(batch, base, read, write))
+    }
+
+    fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+        self.run(|bus, batch, base| bus.transfer_bytes_in_place(batch, base, words))
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// Bit-banged SPI device with a chip select pin on the same device as the bus
+///
+/// The chip select pin is active-low.
+#[derive(Debug)]
+pub struct BitBangDevice<P, D> {
+    bus: BitBangBus<P>,
+    cs: PinFlag,
+    delay: D,
+}
+
+impl<P: Port, D: DelayNs> BitBangDevice<P, D> {
+    /// Create a new device. No I²C communication is done.
+    ///
+    /// The delay is used for `Operation::DelayNs` within transactions.
+    pub fn new(bus: BitBangBus<P>, cs: PinFlag, delay: D) -> Self {
+        BitBangDevice { bus, cs, delay }
+    }
+
+    /// Destroy the device instance, return the bus and the delay.
+    pub fn destroy(self) -> (BitBangBus<P>, D) {
+        (self.bus, self.delay)
+    }
+
+    fn run_operations(
+        &mut self,
+        batch: &mut Batch,
+        base: u16,
+        operations: &mut [Operation<'_, u8>],
+    ) -> Result<(), Error<P::BusError>> {
+        let cs = self.cs.mask;
+        let bus = &mut self.bus;
+        // make sure the clock is idle before selecting the device
+        if bus.port.last_set() != base | cs {
+            batch.push(&mut bus.port, base | cs)?;
+        }
+        batch.push(&mut bus.port, base)?;
+        for operation in operations {
+            match operation {
+                Operation::Read(words) => bus.transfer_bytes(batch, base, words, &[])?,
+                Operation::Write(words) => bus.transfer_bytes(batch, base, &mut [], words)?,
+                Operation::Transfer(read, write) => bus.transfer_bytes(batch, base, read, write)?,
+                Operation::TransferInPlace(words) => {
+                    bus.transfer_bytes_in_place(batch, base, words)?
+                }
+                Operation::DelayNs(ns) => {
+                    batch.flush(&mut bus.port)?;
+                    self.delay.delay_ns(*ns);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<P, D> ErrorType for BitBangDevice<P, D>
+where
+    P: Port,
+    P::BusError: core::fmt::Debug,
+{
+    type Error = Error<P::BusError>;
+}
+
+impl<P, D> SpiDevice for BitBangDevice<P, D>
+where
+    P: Port,
+    P::BusError: core::fmt::Debug,
+    D: DelayNs,
+{
+    fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), Self::Error> {
+        let cs = self.cs.mask;
+        let mut batch = Batch::new();
+        let base = self.bus.base(cs);
+        let result = self.run_operations(&mut batch, base, operations);
+        // deassert even if the transfer failed, the first error is returned
+        let port = &mut self.bus.port;
+        let last = batch.last.unwrap_or(base);
+        let deselect = batch.push(port, last | cs).and_then(|_| batch.flush(port));
+        result.and(deselect)
+    }
+}
@@ -4,6 +4,7 @@ pub use embedded_hal::digital::InputPin;
 
 use super::{Error, PinFlag};
 use core::marker::PhantomData;
+use embedded_hal::delay::DelayNs;
 
 macro_rules! pins {
     ( $( $PX:ident ),+ ) => {
@@ -59,6 +60,14 @@ pub trait SetPin<E: core::fmt::Debug> {
     fn set_pin_high(&self, pin_flag: PinFlag) -> Result<(), Error<E>>;
     /// Set a pin low
     fn set_pin_low(&self, pin_flag: PinFlag) -> Result<(), Error<E>>;
+    /// Drive a pin to a level for at least `duration_ns` nanoseconds, then restore it
+    fn pulse_pin<D: DelayNs>(
+        &self,
+        pin_flag: PinFlag,
+        high: bool,
+        duration_ns: u32,
+        delay: &mut D,
+    ) -> Result<(), Error<E>>;
 }
 
 /// Read if a pin is high or low
@@ -88,6 +97,20 @@ macro_rules! io_pin_impl {
                 }
             }
 
+            impl<'a, S, E: core::fmt::Debug> $PX<'a, S, E>
+            where S: SetPin<E> {
+                /// Drive the pin to a level for at least `duration_ns` nanoseconds,
+                /// then restore its previous status (e.g. to pulse a reset line).
+                pub fn pulse<D: DelayNs>(
+                    &mut self,
+                    high: bool,
+                    duration_ns: u32,
+                    delay: &mut D,
+                ) -> Result<(), Error<E>> {
+                    self.0.pulse_pin(PinFlag::$PX, high, duration_ns, delay)
+                }
+            }
+
 
             impl<'a, S, E: core::fmt::Debug> InputPin for $PX<'a, S, E>
             where S: GetPin<E> {
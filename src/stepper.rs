@@ -0,0 +1,272 @@
+//! Stepper motors
+//!
+//! Drives unipolar stepper motors through driver boards like the ULN2003 (e.g. the
+//! common 28BYJ-48), or bipolar stepper motors through an H-bridge, using four pins
+//! per motor. Usually a nibble of the device is used for each motor, so up to two
+//! motors can be driven with a PCF8574 and up to four with a PCF8575.
+//!
+//! The four pins are given in coil sequence order: A, B, C, D for unipolar motors,
+//! or A+, B+, A-, B- for bipolar motors.
+//!
+//! Each motor has its own step mode, position, target position and step interval.
+//! `tick()` must be called periodically with a timestamp. Every motor that is due
+//! moves one step towards its target and all the motors on the device are updated
+//! with a single `set()`. The pins not used by the motors keep their status.
+//!
+//! Time is provided by the caller as a free-running `u32` timestamp or tick count
+//! (e.g. microseconds). Wrap-around is handled.
+//!
+//! ```no_run
+//! use linux_embedded_hal::I2cdev;
+//! use pcf857x::{Pcf8574, PinFlag, SlaveAddr};
+//! use pcf857x::stepper::{StepMode, Steppers};
+//!
+//! let dev = I2cdev::new("/dev/i2c-1").unwrap();
+//! let expander = Pcf8574::new(dev, SlaveAddr::default());
+//! let mut motors = Steppers::new(
+//!     expander,
+//!     [
+//!         [PinFlag::P0, PinFlag::P1, PinFlag::P2, PinFlag::P3],
+//!         [PinFlag::P4, PinFlag::P5, PinFlag::P6, PinFlag::P7],
+//!     ],
+//! );
+//! motors.set_mode(0, StepMode::Half).unwrap();
+//! motors.set_step_interval(0, 1000).unwrap();
+//! motors.move_to(0, 4096).unwrap();
+//! motors.move_to(1, -200).unwrap();
+//! # let mut now = 0;
+//! while motors.is_moving(0).unwrap() || motors.is_moving(1).unwrap() {
+//!     motors.tick(now).unwrap();
+//!     # now += 1;
+//! }
+//! ```
+
+use crate::{Error, PinFlag, Port};
+
+/// Coil patterns of the half-step sequence. The even positions energize
+/// a single coil (wave drive) and the odd ones two coils (full step).
+const HALF_STEP_SEQUENCE: [u8; 8] = [
+    0b0001, 0b0011, 0b0010, 0b0110, 0b0100, 0b1100, 0b1000, 0b1001,
+];
+
+/// Step mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepMode {
+    /// One coil energized at a time (lower torque and power consumption)
+    Wave,
+    /// Two coils energized at a time (highest torque)
+    Full,
+    /// Alternating one and two coils energized (double resolution)
+    Half,
+}
+
+/// Rotation direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Coil sequence forward, increasing the position
+    Forward,
+    /// Coil sequence backward, decreasing the position
+    Backward,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Motor {
+    pins: [u16; 4],
+    mode: StepMode,
+    phase: usize,
+    position: i32,
+    target: i32,
+    step_interval: u32,
+    last_step_at: u32,
+    energized: bool,
+}
+
+impl Motor {
+    fn step(&mut self, direction: Direction) {
+        let increment = match self.mode {
+            StepMode::Half => 1,
+            StepMode::Wave | StepMode::Full => 2,
+        };
+        self.phase = match direction {
+            Direction::Forward => (self.phase + increment) % 8,
+            Direction::Backward => (self.phase + 8 - increment) % 8,
+        };
+        self.position = match direction {
+            Direction::Forward => self.position.wrapping_add(1),
+            Direction::Backward => self.position.wrapping_sub(1),
+        };
+        self.energized = true;
+    }
+
+    fn mask(&self) -> u16 {
+        self.pins.iter().fold(0, |mask, pin| mask | pin)
+    }
+
+    fn coils(&self) -> u16 {
+        if !self.energized {
+            return 0;
+        }
+        let pattern = HALF_STEP_SEQUENCE[self.phase];
+        self.pins
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| pattern & (1 << i) != 0)
+            .fold(0, |word, (_, pin)| word | pin)
+    }
+}
+
+/// Group of `N` stepper motors on a device
+#[derive(Debug)]
+pub struct Steppers<P, const N: usize> {
+    port: P,
+    motors: [Motor; N],
+}
+
+impl<P: Port, const N: usize> Steppers<P, N> {
+    /// Create a new group of motors with the pins of each motor.
+    ///
+    /// The motors start in full step mode at position 0 with the coils released.
+    /// No I²C communication is done.
+    pub fn new(port: P, pins: [[PinFlag; 4]; N]) -> Self {
+        Steppers {
+            port,
+            motors: pins.map(|pins| Motor {
+                pins: pins.map(|pin| pin.mask),
+                mode: StepMode::Full,
+                phase: 1,
+                position: 0,
+                target: 0,
+                step_interval: 0,
+                last_step_at: 0,
+                energized: false,
+            }),
+        }
+    }
+
+    /// Destroy the motor group, return the device.
+    pub fn destroy(self) -> P {
+        self.port
+    }
+
+    /// Set the step mode of a motor.
+    pub fn set_mode(&mut self, motor: usize, mode: StepMode) -> Result<(), Error<P::BusError>> {
+        let motor = self.motor(motor)?;
+        motor.mode = mode;
+        // wave drive uses the even positions of the sequence and full step the odd ones
+        match (mode, motor.phase % 2) {
+            (StepMode::Wave, 1) | (StepMode::Full, 0) => motor.phase = (motor.phase + 1) % 8,
+            _ => (),
+        }
+        Ok(())
+    }
+
+    /// Set the minimum time between steps of a motor, in the same units as
+    /// the timestamps passed to `tick()`.
+    pub fn set_step_interval(
+        &mut self,
+        motor: usize,
+        interval: u32,
+    ) -> Result<(), Error<P::BusError>> {
+        self.motor(motor)?.step_interval = interval;
+        Ok(())
+    }
+
+    /// Set the target position of a motor, in steps.
+    pub fn move_to(&mut self, motor: usize, position: i32) -> Result<(), Error<P::BusError>> {
+        self.motor(motor)?.target = position;
+        Ok(())
+    }
+
+    /// Move a motor a number of steps relative to its target position.
+    /// Negative values move backward.
+    pub fn move_by(&mut self, motor: usize, steps: i32) -> Result<(), Error<P::BusError>> {
+        let motor = self.motor(motor)?;
+        motor.target = motor.target.wrapping_add(steps);
+        Ok(())
+    }
+
+    /// Stop a motor at its current position.
+    pub fn stop(&mut self, motor: usize) -> Result<(), Error<P::BusError>> {
+        let motor = self.motor(motor)?;
+        motor.target = motor.position;
+        Ok(())
+    }
+
+    /// Set the current position of a motor without moving it (e.g. after homing).
+    pub fn set_position(&mut self, motor: usize, position: i32) -> Result<(), Error<P::BusError>> {
+        let motor = self.motor(motor)?;
+        motor.position = position;
+        motor.target = position;
+        Ok(())
+    }
+
+    /// Current position of a motor, in steps
+    pub fn position(&self, motor: usize) -> Result<i32, Error<P::BusError>> {
+        self.motors
+            .get(motor)
+            .map(|motor| motor.position)
+            .ok_or(Error::InvalidInputData)
+    }
+
+    /// Whether a motor has not reached its target position yet
+    pub fn is_moving(&self, motor: usize) -> Result<bool, Error<P::BusError>> {
+        self.motors
+            .get(motor)
+            .map(|motor| motor.position != motor.target)
+            .ok_or(Error::InvalidInputData)
+    }
+
+    /// Move a motor one step immediately, regardless of its target position and
+    /// step interval. The target position moves along.
+    pub fn step(&mut self, motor: usize, direction: Direction) -> Result<(), Error<P::BusError>> {
+        let motor = self.motor(motor)?;
+        motor.step(direction);
+        motor.target = motor.position;
+        self.write()
+    }
+
+    /// Step every motor that is due towards its target position.
+    ///
+    /// If any motor moved, all motors are updated with a single `set()`.
+    /// Returns whether any motor moved.
+    pub fn tick(&mut self, now: u32) -> Result<bool, Error<P::BusError>> {
+        let mut moved = false;
+        for motor in &mut self.motors {
+            if motor.position != motor.target
+                && now.wrapping_sub(motor.last_step_at) >= motor.step_interval
+            {
+                let direction = if motor.target > motor.position {
+                    Direction::Forward
+                } else {
+                    Direction::Backward
+                };
+                motor.step(direction);
+                motor.last_step_at = now;
+                moved = true;
+            }
+        }
+        if moved {
+            self.write()?;
+        }
+        Ok(moved)
+    }
+
+    /// Switch off the coils of a motor to save power. The holding torque is lost.
+    /// The coils are energized again with the next step.
+    pub fn release(&mut self, motor: usize) -> Result<(), Error<P::BusError>> {
+        self.motor(motor)?.energized = false;
+        self.write()
+    }
+
+    fn motor(&mut self, motor: usize) -> Result<&mut Motor, Error<P::BusError>> {
+        self.motors.get_mut(motor).ok_or(Error::InvalidInputData)
+    }
+
+    fn write(&mut self) -> Result<(), Error<P::BusError>> {
+        let (mask, coils) = self.motors.iter().fold((0, 0), |(mask, coils), motor| {
+            (mask | motor.mask(), coils | motor.coils())
+        });
+        let bits = (self.port.last_set() & !mask) | coils;
+        self.port.set(bits)
+    }
+}
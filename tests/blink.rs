@@ -0,0 +1,147 @@
+use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTrans};
+use pcf857x::blink::{Blinker, Pattern, Repeat, Step};
+use pcf857x::{Error, Pcf8574, PinFlag, SlaveAddr};
+mod base;
+
+const DEV_ADDR: u8 = 0b010_0000;
+
+fn new(transactions: &[I2cTrans]) -> Blinker<'static, Pcf8574<I2cMock>, 2> {
+    let expander = Pcf8574::new(I2cMock::new(transactions), SlaveAddr::default());
+    Blinker::new(expander, [PinFlag::P0, PinFlag::P1])
+}
+
+fn writes(values: &[u8]) -> Vec<I2cTrans> {
+    values
+        .iter()
+        .map(|v| I2cTrans::write(DEV_ADDR, vec![*v]))
+        .collect()
+}
+
+const BLINK: [Step; 2] = [Step::on(10), Step::off(20)];
+
+#[test]
+fn steady() {
+    let mut leds = new(&writes(&[0b01, 0b10]));
+    leds.set_pattern(0, Pattern::On, 0).unwrap();
+    leds.tick(0).unwrap();
+    leds.tick(1000).unwrap();
+    leds.set_pattern(0, Pattern::Off, 1000).unwrap();
+    leds.set_pattern(1, Pattern::On, 1000).unwrap();
+    leds.tick(1000).unwrap();
+    assert!(!leds.is_finished(1).unwrap());
+    leds.destroy().destroy().done();
+}
+
+#[test]
+fn steps_repeat_forever() {
+    let mut leds = new(&writes(&[0b01, 0b00, 0b01, 0b00]));
+    leds.set_pattern(0, Pattern::Steps(&BLINK, Repeat::Forever), 0)
+        .unwrap();
+    for now in [0, 9, 10, 29, 30, 39, 40] {
+        leds.tick(now).unwrap();
+    }
+    assert!(!leds.is_finished(0).unwrap());
+    leds.destroy().destroy().done();
+}
+
+#[test]
+fn steps_repeat_times() {
+    let mut leds = new(&writes(&[0b10, 0b00, 0b10, 0b00]));
+    leds.set_pattern(1, Pattern::Steps(&BLINK, Repeat::Times(2)), 0)
+        .unwrap();
+    for now in [0, 10, 30, 40, 60, 1000] {
+        leds.tick(now).unwrap();
+    }
+    assert!(leds.is_finished(1).unwrap());
+    leds.destroy().destroy().done();
+}
+
+#[test]
+fn late_tick_skips_steps_without_drift() {
+    let mut leds = new(&writes(&[0b01, 0b00, 0b01]));
+    leds.set_pattern(0, Pattern::Steps(&BLINK, Repeat::Forever), 0)
+        .unwrap();
+    leds.tick(0).unwrap();
+    // second cycle, off step
+    leds.tick(55).unwrap();
+    leds.tick(60).unwrap();
+    leds.destroy().destroy().done();
+}
+
+#[test]
+fn code() {
+    // ".-": on 10, off 10, on 30, off 100
+    let mut leds = new(&writes(&[0b01, 0b00, 0b01, 0b00, 0b01]));
+    let pattern = Pattern::Code {
+        code: ".-",
+        unit: 10,
+        pause: 100,
+        repeat: Repeat::Forever,
+    };
+    leds.set_pattern(0, pattern, 0).unwrap();
+    for now in [0, 10, 20, 49, 50, 149, 150] {
+        leds.tick(now).unwrap();
+    }
+    leds.destroy().destroy().done();
+}
+
+#[test]
+fn one_write_per_tick() {
+    let mut leds = new(&writes(&[0b11, 0b00]));
+    leds.set_pattern(0, Pattern::Steps(&BLINK, Repeat::Forever), 0)
+        .unwrap();
+    leds.set_pattern(1, Pattern::Steps(&BLINK, Repeat::Forever), 0)
+        .unwrap();
+    leds.tick(0).unwrap();
+    leds.tick(10).unwrap();
+    leds.destroy().destroy().done();
+}
+
+#[test]
+fn wraps_around() {
+    let start = u32::MAX - 5;
+    let mut leds = new(&writes(&[0b01, 0b00]));
+    leds.set_pattern(0, Pattern::Steps(&BLINK, Repeat::Forever), start)
+        .unwrap();
+    leds.tick(start).unwrap();
+    leds.tick(3).unwrap();
+    leds.tick(4).unwrap();
+    leds.destroy().destroy().done();
+}
+
+#[test]
+fn keeps_other_pins() {
+    let mut expander = Pcf8574::new(
+        I2cMock::new(&writes(&[0x80, 0x81, 0x80])),
+        SlaveAddr::default(),
+    );
+    expander.set(0x80).unwrap();
+    let mut leds = Blinker::new(&mut expander, [PinFlag::P0]);
+    leds.set_pattern(0, Pattern::Steps(&BLINK, Repeat::Times(1)), 0)
+        .unwrap();
+    leds.tick(0).unwrap();
+    leds.tick(10).unwrap();
+    expander.destroy().done();
+}
+
+#[test]
+fn invalid_patterns() {
+    let mut leds = new(&[]);
+    let code = |code, unit| Pattern::Code {
+        code,
+        unit,
+        pause: 10,
+        repeat: Repeat::Forever,
+    };
+    expect_err!(leds.set_pattern(0, code("..x", 10), 0), InvalidInputData);
+    expect_err!(leds.set_pattern(0, code("", 10), 0), InvalidInputData);
+    expect_err!(leds.set_pattern(0, code("...", 0), 0), InvalidInputData);
+    let zero = [Step::on(0), Step::off(0)];
+    expect_err!(
+        leds.set_pattern(0, Pattern::Steps(&zero, Repeat::Forever), 0),
+        InvalidInputData
+    );
+    expect_err!(leds.set_pattern(2, Pattern::On, 0), InvalidInputData);
+    expect_err!(leds.is_finished(2), InvalidInputData);
+    leds.destroy().destroy().done();
+}
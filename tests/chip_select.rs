@@ -0,0 +1,157 @@
+use embedded_hal::i2c::ErrorKind;
+use embedded_hal::spi::{Operation, SpiDevice};
+use embedded_hal_mock::eh1::delay::NoopDelay;
+use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTrans};
+use embedded_hal_mock::eh1::spi::{Mock as SpiMock, Transaction as SpiTrans};
+use pcf857x::chip_select::{ChipSelectError, SharedBus};
+use pcf857x::{Error, Pcf8574, SlaveAddr};
+
+const DEV_ADDR: u8 = 0b010_0000;
+
+fn writes(values: &[u8]) -> Vec<I2cTrans> {
+    values
+        .iter()
+        .map(|v| I2cTrans::write(DEV_ADDR, vec![*v]))
+        .collect()
+}
+
+#[test]
+fn creating_device_deasserts_chip_select() {
+    let expander = Pcf8574::new(I2cMock::new(&writes(&[0b01])), SlaveAddr::default());
+    let bus = SharedBus::new(SpiMock::new(&[]));
+    {
+        let parts = expander.split();
+        bus.device(parts.p0, NoopDelay::new()).unwrap();
+    }
+    bus.destroy().done();
+    expander.destroy().done();
+}
+
+#[test]
+fn transaction_selects_device_keeping_other_pins() {
+    let transactions = writes(&[0b1000_0000, 0b1000_0001, 0b1000_0000, 0b1000_0001]);
+    let mut expander = Pcf8574::new(I2cMock::new(&transactions), SlaveAddr::default());
+    expander.set(0b1000_0000).unwrap();
+    let bus = SharedBus::new(SpiMock::new(&[
+        SpiTrans::write_vec(vec![0x03, 0x00]),
+        SpiTrans::read_vec(vec![0xAB, 0xCD]),
+        SpiTrans::flush(),
+    ]));
+    {
+        let parts = expander.split();
+        let mut device = bus.device(parts.p0, NoopDelay::new()).unwrap();
+        let mut data = [0; 2];
+        device
+            .transaction(&mut [Operation::Write(&[0x03, 0x00]), Operation::Read(&mut data)])
+            .unwrap();
+        assert_eq!([0xAB, 0xCD], data);
+    }
+    bus.destroy().done();
+    expander.destroy().done();
+}
+
+#[test]
+fn only_one_chip_select_is_active_at_a_time() {
+    let transactions = writes(&[0b01, 0b11, 0b10, 0b11, 0b01, 0b11]);
+    let expander = Pcf8574::new(I2cMock::new(&transactions), SlaveAddr::default());
+    let bus = SharedBus::new(SpiMock::new(&[
+        SpiTrans::write_vec(vec![1]),
+        SpiTrans::flush(),
+        SpiTrans::write_vec(vec![2]),
+        SpiTrans::flush(),
+    ]));
+    {
+        let parts = expander.split();
+        let mut first = bus.device(parts.p0, NoopDelay::new()).unwrap();
+        let mut second = bus.device(parts.p1, NoopDelay::new()).unwrap();
+        first.write(&[1]).unwrap();
+        second.write(&[2]).unwrap();
+    }
+    bus.destroy().done();
+    expander.destroy().done();
+}
+
+#[test]
+fn delay_flushes_bus() {
+    let transactions = writes(&[0b01, 0b00, 0b01]);
+    let expander = Pcf8574::new(I2cMock::new(&transactions), SlaveAddr::default());
+    let bus = SharedBus::new(SpiMock::new(&[
+        SpiTrans::write_vec(vec![1]),
+        SpiTrans::flush(),
+        SpiTrans::write_vec(vec![2]),
+        SpiTrans::flush(),
+    ]));
+    {
+        let parts = expander.split();
+        let mut device = bus.device(parts.p0, NoopDelay::new()).unwrap();
+        device
+            .transaction(&mut [
+                Operation::Write(&[1]),
+                Operation::DelayNs(1000),
+                Operation::Write(&[2]),
+            ])
+            .unwrap();
+    }
+    bus.destroy().done();
+    expander.destroy().done();
+}
+
+#[test]
+fn chip_select_error_aborts_transaction() {
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, vec![0b01]),
+        I2cTrans::write(DEV_ADDR, vec![0b00]).with_error(ErrorKind::Other),
+    ];
+    let expander = Pcf8574::new(I2cMock::new(&transactions), SlaveAddr::default());
+    let bus = SharedBus::new(SpiMock::new(&[]));
+    {
+        let parts = expander.split();
+        let mut device = bus.device(parts.p0, NoopDelay::new()).unwrap();
+        match device.write(&[1]) {
+            Err(ChipSelectError::ChipSelect(Error::I2C(ErrorKind::Other))) => (),
+            _ => panic!(),
+        }
+    }
+    bus.destroy().done();
+    expander.destroy().done();
+}
+
+#[test]
+fn stuck_chip_select_blocks_other_devices_until_deasserted() {
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, vec![0b01]),
+        I2cTrans::write(DEV_ADDR, vec![0b11]),
+        I2cTrans::write(DEV_ADDR, vec![0b10]),
+        I2cTrans::write(DEV_ADDR, vec![0b11]).with_error(ErrorKind::Other),
+        I2cTrans::write(DEV_ADDR, vec![0b10]),
+        I2cTrans::write(DEV_ADDR, vec![0b11]),
+        I2cTrans::write(DEV_ADDR, vec![0b01]),
+        I2cTrans::write(DEV_ADDR, vec![0b11]),
+    ];
+    let expander = Pcf8574::new(I2cMock::new(&transactions), SlaveAddr::default());
+    let bus = SharedBus::new(SpiMock::new(&[
+        SpiTrans::write_vec(vec![1]),
+        SpiTrans::flush(),
+        SpiTrans::write_vec(vec![2]),
+        SpiTrans::flush(),
+        SpiTrans::write_vec(vec![3]),
+        SpiTrans::flush(),
+    ]));
+    {
+        let parts = expander.split();
+        let mut first = bus.device(parts.p0, NoopDelay::new()).unwrap();
+        let mut second = bus.device(parts.p1, NoopDelay::new()).unwrap();
+        match first.write(&[1]) {
+            Err(ChipSelectError::ChipSelect(Error::I2C(ErrorKind::Other))) => (),
+            _ => panic!(),
+        }
+        match second.write(&[2]) {
+            Err(ChipSelectError::Busy) => (),
+            _ => panic!(),
+        }
+        first.write(&[2]).unwrap();
+        second.write(&[3]).unwrap();
+    }
+    bus.destroy().done();
+    expander.destroy().done();
+}
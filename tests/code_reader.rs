@@ -0,0 +1,108 @@
+use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTrans};
+use pcf857x::code_reader::{BcdThumbwheel, DipSwitch, GrayCode};
+use pcf857x::{Error, Pcf8574, Pcf8575, PinFlag, SlaveAddr};
+mod base;
+
+const DEV_ADDR: u8 = 0b010_0000;
+
+fn get_8574(mask: u8, value: u8) -> [I2cTrans; 2] {
+    [
+        I2cTrans::write(DEV_ADDR, vec![mask]),
+        I2cTrans::read(DEV_ADDR, vec![value]),
+    ]
+}
+
+fn get_8575(mask: u16, value: u16) -> [I2cTrans; 2] {
+    [
+        I2cTrans::write(DEV_ADDR, vec![mask as u8, (mask >> 8) as u8]),
+        I2cTrans::read(DEV_ADDR, vec![value as u8, (value >> 8) as u8]),
+    ]
+}
+
+#[test]
+fn dip_switch_pin_mapping() {
+    let expander = Pcf8574::new(
+        I2cMock::new(&get_8574(0xF0, 0b1010_0000)),
+        SlaveAddr::default(),
+    );
+    let mut switch = DipSwitch::new(
+        expander,
+        [PinFlag::P7, PinFlag::P6, PinFlag::P5, PinFlag::P4],
+    );
+    assert_eq!(0b0101, switch.read().unwrap());
+    switch.destroy().destroy().done();
+}
+
+#[test]
+fn dip_switch_inverted() {
+    let expander = Pcf8574::new(
+        I2cMock::new(&get_8574(0x0F, 0b1111_1101)),
+        SlaveAddr::default(),
+    );
+    let mut switch = DipSwitch::new(
+        expander,
+        [PinFlag::P0, PinFlag::P1, PinFlag::P2, PinFlag::P3],
+    );
+    switch.set_inversion_mask(0x0F);
+    assert_eq!(0b0010, switch.read().unwrap());
+    switch.destroy().destroy().done();
+}
+
+fn thumbwheel(transactions: &[I2cTrans]) -> BcdThumbwheel<Pcf8575<I2cMock>, 3> {
+    let expander = Pcf8575::new(I2cMock::new(transactions), SlaveAddr::default());
+    BcdThumbwheel::new(
+        expander,
+        [
+            [PinFlag::P10, PinFlag::P11, PinFlag::P12, PinFlag::P13],
+            [PinFlag::P14, PinFlag::P15, PinFlag::P16, PinFlag::P17],
+            [PinFlag::P0, PinFlag::P1, PinFlag::P2, PinFlag::P3],
+        ],
+    )
+}
+
+#[test]
+fn bcd_thumbwheel() {
+    let mut wheel = thumbwheel(&get_8575(0xFF0F, 0x4907));
+    assert_eq!(947, wheel.read().unwrap());
+    wheel.destroy().destroy().done();
+}
+
+#[test]
+fn bcd_thumbwheel_inverted() {
+    let mut wheel = thumbwheel(&get_8575(0xFF0F, !0x0102));
+    wheel.set_inversion_mask(0xFFFF);
+    assert_eq!(102, wheel.read().unwrap());
+    wheel.destroy().destroy().done();
+}
+
+#[test]
+fn bcd_thumbwheel_rejects_invalid_digits() {
+    let mut wheel = thumbwheel(&get_8575(0xFF0F, 0x1A00));
+    expect_err!(wheel.read(), InvalidInputData);
+    wheel.destroy().destroy().done();
+}
+
+#[test]
+fn gray_code() {
+    let pins = [PinFlag::P0, PinFlag::P1, PinFlag::P2, PinFlag::P3];
+    let gray = [0b0000, 0b0001, 0b0011, 0b0010, 0b0110, 0b1000];
+    let expected = [0, 1, 2, 3, 4, 15];
+    let transactions: Vec<_> = gray.iter().flat_map(|code| get_8574(0x0F, *code)).collect();
+    let expander = Pcf8574::new(I2cMock::new(&transactions), SlaveAddr::default());
+    let mut position = GrayCode::new(expander, pins);
+    for value in expected {
+        assert_eq!(value, position.read().unwrap());
+    }
+    position.destroy().destroy().done();
+}
+
+#[test]
+fn gray_code_ignores_other_pins() {
+    let expander = Pcf8574::new(
+        I2cMock::new(&get_8574(0x03, 0b1111_0010)),
+        SlaveAddr::default(),
+    );
+    let mut position = GrayCode::new(expander, [PinFlag::P0, PinFlag::P1]);
+    assert_eq!(3, position.read().unwrap());
+    position.destroy().destroy().done();
+}
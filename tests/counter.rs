@@ -0,0 +1,112 @@
+use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTrans};
+use pcf857x::counter::{Edge, PulseCounter};
+use pcf857x::{Pcf8574, PinFlag, SlaveAddr};
+
+const DEV_ADDR: u8 = 0b010_0000;
+
+fn feed(counter: &mut PulseCounter, snapshots: &[(u16, u32)]) {
+    for (snapshot, now) in snapshots {
+        counter.update(*snapshot, *now);
+    }
+}
+
+#[test]
+fn counts_selected_edges() {
+    let mut counter = PulseCounter::new(PinFlag::P0 | PinFlag::P1 | PinFlag::P2, Edge::Rising);
+    counter.set_edge(PinFlag::P1, Edge::Falling);
+    counter.set_edge(PinFlag::P2, Edge::Both);
+    feed(
+        &mut counter,
+        &[(0, 0), (0b111, 1), (0, 2), (0b111, 3), (0, 4)],
+    );
+    assert_eq!(2, counter.count(PinFlag::P0));
+    assert_eq!(2, counter.count(PinFlag::P1));
+    assert_eq!(4, counter.count(PinFlag::P2));
+    assert_eq!(0, counter.count(PinFlag::P3));
+}
+
+#[test]
+fn first_snapshot_counts_no_edges() {
+    let mut counter = PulseCounter::new(PinFlag::P0, Edge::Rising);
+    feed(&mut counter, &[(1, 0), (1, 1)]);
+    assert_eq!(0, counter.count(PinFlag::P0));
+}
+
+#[test]
+fn ignores_other_pins() {
+    let mut counter = PulseCounter::new(PinFlag::P0, Edge::Both);
+    counter.set_edge(PinFlag::P1, Edge::Both);
+    feed(&mut counter, &[(0, 0), (0b10, 1), (0, 2)]);
+    assert_eq!(0, counter.count(PinFlag::P1));
+}
+
+#[test]
+fn period_and_frequency() {
+    let mut counter = PulseCounter::new(PinFlag::P0, Edge::Rising);
+    feed(&mut counter, &[(0, 0), (1, 100), (0, 120)]);
+    assert_eq!(None, counter.period(PinFlag::P0, 120));
+    assert_eq!(None, counter.frequency(PinFlag::P0, 120, 1000));
+    feed(&mut counter, &[(1, 200), (0, 220)]);
+    assert_eq!(Some(100), counter.period(PinFlag::P0, 250));
+    assert_eq!(Some(10.0), counter.frequency(PinFlag::P0, 250, 1000));
+    // the pulses stop
+    assert_eq!(Some(500), counter.period(PinFlag::P0, 700));
+    assert_eq!(Some(2.0), counter.frequency(PinFlag::P0, 700, 1000));
+}
+
+#[test]
+fn period_of_both_edges_is_a_full_cycle() {
+    let mut counter = PulseCounter::new(PinFlag::P0, Edge::Both);
+    feed(&mut counter, &[(0, 0), (1, 100), (0, 110)]);
+    assert_eq!(None, counter.period(PinFlag::P0, 110));
+    feed(&mut counter, &[(1, 200)]);
+    assert_eq!(Some(100), counter.period(PinFlag::P0, 205));
+    feed(&mut counter, &[(0, 210)]);
+    assert_eq!(Some(100), counter.period(PinFlag::P0, 220));
+    assert_eq!(Some(10.0), counter.frequency(PinFlag::P0, 220, 1000));
+    // the pulses stop
+    assert_eq!(Some(500), counter.period(PinFlag::P0, 700));
+}
+
+#[test]
+fn period_across_timestamp_wrap_around() {
+    let mut counter = PulseCounter::new(PinFlag::P0, Edge::Rising);
+    let start = u32::MAX - 30;
+    feed(
+        &mut counter,
+        &[(0, start), (1, start + 10), (0, start + 20), (1, 19)],
+    );
+    assert_eq!(Some(40), counter.period(PinFlag::P0, 20));
+}
+
+#[test]
+fn reset_clears_count_and_period() {
+    let mut counter = PulseCounter::new(PinFlag::P0, Edge::Both);
+    counter.update(0, 0);
+    let mut level = 0;
+    for now in 0..10 {
+        level ^= 1;
+        counter.update(level, now);
+    }
+    assert_eq!(10, counter.count(PinFlag::P0));
+    counter.reset(PinFlag::P0);
+    assert_eq!(0, counter.count(PinFlag::P0));
+    assert_eq!(None, counter.period(PinFlag::P0, 10));
+}
+
+#[test]
+fn polls_with_single_get() {
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, vec![0b11]),
+        I2cTrans::read(DEV_ADDR, vec![0b00]),
+        I2cTrans::write(DEV_ADDR, vec![0b11]),
+        I2cTrans::read(DEV_ADDR, vec![0b11]),
+    ];
+    let mut expander = Pcf8574::new(I2cMock::new(&transactions), SlaveAddr::default());
+    let mut counter = PulseCounter::new(PinFlag::P0 | PinFlag::P1, Edge::Rising);
+    counter.poll(&mut expander, 0).unwrap();
+    counter.poll(&mut expander, 1).unwrap();
+    assert_eq!(1, counter.count(PinFlag::P0));
+    assert_eq!(1, counter.count(PinFlag::P1));
+    expander.destroy().done();
+}
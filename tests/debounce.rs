@@ -0,0 +1,85 @@
+use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTrans};
+use pcf857x::{debounce::Debouncer, Pcf8574, Pcf8575, PinFlag, SlaveAddr};
+
+const DEV_ADDR: u8 = 0b010_0000;
+
+fn reads(values: &[u8]) -> Vec<I2cTrans> {
+    values
+        .iter()
+        .flat_map(|v| {
+            [
+                I2cTrans::write(DEV_ADDR, vec![0b0000_0011]),
+                I2cTrans::read(DEV_ADDR, vec![*v]),
+            ]
+        })
+        .collect()
+}
+
+#[test]
+fn first_poll_sets_initial_state_without_events() {
+    let mut expander = Pcf8574::new(I2cMock::new(&reads(&[0b0000_0001])), SlaveAddr::default());
+    let mut buttons = Debouncer::new(&mut expander, PinFlag::P0 | PinFlag::P1, 20);
+    assert!(buttons.poll(0).unwrap().is_empty());
+    assert!(buttons.is_high(PinFlag::P0));
+    assert!(!buttons.is_high(PinFlag::P1));
+    expander.destroy().done();
+}
+
+#[test]
+fn change_is_reported_after_debounce_time() {
+    let transactions = reads(&[0, 0b10, 0b10, 0b10]);
+    let expander = Pcf8574::new(I2cMock::new(&transactions), SlaveAddr::default());
+    let mut buttons = Debouncer::new(expander, PinFlag::P0 | PinFlag::P1, 20);
+    buttons.poll(0).unwrap();
+    assert!(buttons.poll(5).unwrap().is_empty());
+    assert!(buttons.poll(24).unwrap().is_empty());
+    let events = buttons.poll(25).unwrap();
+    assert!(events.was_pressed(PinFlag::P1));
+    assert!(!events.was_pressed(PinFlag::P0));
+    assert_eq!(0b10, buttons.state());
+    buttons.destroy().destroy().done();
+}
+
+#[test]
+fn bounces_restart_the_debounce_time() {
+    let transactions = reads(&[0b01, 0b00, 0b01, 0b00, 0b00, 0b00]);
+    let expander = Pcf8574::new(I2cMock::new(&transactions), SlaveAddr::default());
+    let mut buttons = Debouncer::new(expander, PinFlag::P0 | PinFlag::P1, 10);
+    buttons.poll(0).unwrap();
+    assert!(buttons.poll(2).unwrap().is_empty());
+    assert!(buttons.poll(4).unwrap().is_empty());
+    assert!(buttons.poll(6).unwrap().is_empty());
+    assert!(buttons.poll(15).unwrap().is_empty());
+    let events = buttons.poll(16).unwrap();
+    assert!(events.was_released(PinFlag::P0));
+    assert_eq!(0, buttons.state());
+    buttons.destroy().destroy().done();
+}
+
+#[test]
+fn handles_timestamp_wrap_around() {
+    let transactions = reads(&[0, 0b01, 0b01]);
+    let expander = Pcf8574::new(I2cMock::new(&transactions), SlaveAddr::default());
+    let mut buttons = Debouncer::new(expander, PinFlag::P0 | PinFlag::P1, 10);
+    buttons.poll(u32::MAX - 20).unwrap();
+    buttons.poll(u32::MAX - 5).unwrap();
+    assert!(buttons.poll(4).unwrap().was_pressed(PinFlag::P0));
+    buttons.destroy().destroy().done();
+}
+
+#[test]
+fn can_debounce_pcf8575_upper_pins() {
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, vec![0, 0x80]),
+        I2cTrans::read(DEV_ADDR, vec![0, 0]),
+        I2cTrans::write(DEV_ADDR, vec![0, 0x80]),
+        I2cTrans::read(DEV_ADDR, vec![0xFF, 0x80]),
+    ];
+    let expander = Pcf8575::new(I2cMock::new(&transactions), SlaveAddr::default());
+    let mut buttons = Debouncer::new(expander, PinFlag::P17, 0);
+    buttons.poll(0).unwrap();
+    let events = buttons.poll(1).unwrap();
+    assert_eq!(0x8000, events.pressed);
+    assert_eq!(0x8000, buttons.state());
+    buttons.destroy().destroy().done();
+}
@@ -0,0 +1,148 @@
+use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTrans};
+use pcf857x::encoder::{EncoderPins, Encoders};
+use pcf857x::{Error, Pcf8574, PinFlag, SlaveAddr};
+
+mod base;
+
+const DEV_ADDR: u8 = 0b010_0000;
+
+/// A/B sequence on P0/P1 for one detent forward, starting and ending at rest (00)
+const FORWARD: [u16; 4] = [0b10, 0b11, 0b01, 0b00];
+const BACKWARD: [u16; 4] = [0b01, 0b11, 0b10, 0b00];
+
+type Decoder = Encoders<Pcf8574<I2cMock>, 2>;
+
+fn new_with(transactions: &[I2cTrans]) -> Decoder {
+    let expander = Pcf8574::new(I2cMock::new(transactions), SlaveAddr::default());
+    Encoders::new(
+        expander,
+        [
+            EncoderPins::new(PinFlag::P1, PinFlag::P0),
+            EncoderPins::new(PinFlag::P5, PinFlag::P4).with_switch(PinFlag::P6),
+        ],
+    )
+}
+
+fn new() -> Decoder {
+    new_with(&[])
+}
+
+fn feed(encoders: &mut Decoder, snapshots: &[u16]) {
+    for snapshot in snapshots {
+        encoders.update(*snapshot);
+    }
+}
+
+#[test]
+fn counts_detents() {
+    let mut encoders = new();
+    encoders.update(0);
+    feed(&mut encoders, &FORWARD);
+    feed(&mut encoders, &FORWARD);
+    assert_eq!(2, encoders.position(0).unwrap());
+    feed(&mut encoders, &BACKWARD);
+    assert_eq!(1, encoders.position(0).unwrap());
+    assert_eq!(1, encoders.take_delta(0).unwrap());
+    assert_eq!(0, encoders.take_delta(0).unwrap());
+    assert_eq!(0, encoders.position(1).unwrap());
+    encoders.destroy().destroy().done();
+}
+
+#[test]
+fn partial_detent_does_not_count() {
+    let mut encoders = new();
+    encoders.update(0);
+    feed(&mut encoders, &FORWARD[..3]);
+    assert_eq!(0, encoders.position(0).unwrap());
+    // bouncing back and forth between two states does not accumulate
+    feed(&mut encoders, &[0b11, 0b01, 0b11, 0b01]);
+    assert_eq!(0, encoders.position(0).unwrap());
+    feed(&mut encoders, &FORWARD[3..]);
+    assert_eq!(1, encoders.position(0).unwrap());
+    encoders.destroy().destroy().done();
+}
+
+#[test]
+fn steps_per_detent() {
+    let mut encoders = new();
+    encoders.set_steps_per_detent(0, 2).unwrap();
+    expect_err!(encoders.set_steps_per_detent(0, 0), InvalidInputData);
+    expect_err!(encoders.set_steps_per_detent(0, 5), InvalidInputData);
+    expect_err!(encoders.set_steps_per_detent(2, 2), InvalidInputData);
+    encoders.update(0);
+    feed(&mut encoders, &FORWARD);
+    assert_eq!(2, encoders.position(0).unwrap());
+    encoders.destroy().destroy().done();
+}
+
+#[test]
+fn rejects_invalid_transitions() {
+    let mut encoders = new();
+    encoders.update(0);
+    feed(&mut encoders, &[0b11, 0b00, 0b11, 0b00]);
+    assert_eq!(0, encoders.position(0).unwrap());
+    assert_eq!(4, encoders.invalid_transitions(0).unwrap());
+    encoders.destroy().destroy().done();
+}
+
+#[test]
+fn tracks_several_encoders() {
+    let mut encoders = new();
+    encoders.update(0);
+    for (a, b) in FORWARD.iter().zip(BACKWARD.iter()) {
+        encoders.update(a | (b << 4));
+    }
+    assert_eq!(1, encoders.position(0).unwrap());
+    assert_eq!(-1, encoders.position(1).unwrap());
+    encoders.set_position(1, 10).unwrap();
+    assert_eq!(10, encoders.position(1).unwrap());
+    expect_err!(encoders.position(2), InvalidInputData);
+    expect_err!(encoders.take_delta(2), InvalidInputData);
+    expect_err!(encoders.is_pressed(2), InvalidInputData);
+    encoders.destroy().destroy().done();
+}
+
+#[test]
+fn push_switch() {
+    let mut encoders = new();
+    encoders.update(0);
+    assert!(!encoders.take_pressed(1).unwrap());
+    encoders.update(0x40);
+    assert!(encoders.is_pressed(1).unwrap());
+    encoders.update(0);
+    assert!(!encoders.is_pressed(1).unwrap());
+    assert!(encoders.take_pressed(1).unwrap());
+    assert!(!encoders.take_pressed(1).unwrap());
+    assert!(!encoders.is_pressed(0).unwrap());
+    encoders.destroy().destroy().done();
+}
+
+#[test]
+fn switch_held_at_start_is_not_a_press() {
+    let mut encoders = new();
+    encoders.update(0x40);
+    encoders.update(0x40);
+    assert!(encoders.is_pressed(1).unwrap());
+    assert!(!encoders.take_pressed(1).unwrap());
+    encoders.destroy().destroy().done();
+}
+
+#[test]
+fn polls_all_pins_at_once() {
+    let transactions = FORWARD
+        .iter()
+        .flat_map(|snapshot| {
+            vec![
+                I2cTrans::write(DEV_ADDR, vec![0b0111_0011]),
+                I2cTrans::read(DEV_ADDR, vec![*snapshot as u8]),
+            ]
+        })
+        .collect::<Vec<_>>();
+    let mut encoders = new_with(&transactions);
+    encoders.update(0);
+    for _ in 0..4 {
+        encoders.poll().unwrap();
+    }
+    assert_eq!(1, encoders.position(0).unwrap());
+    encoders.destroy().destroy().done();
+}
@@ -0,0 +1,39 @@
+use embedded_hal::i2c::{ErrorKind, NoAcknowledgeSource};
+use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTrans};
+use pcf857x::{Error, Pcf8574, SlaveAddr};
+
+const DEV_ADDR: u8 = 0b010_0000;
+
+#[test]
+fn exposes_i2c_error_kind() {
+    let nack = ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address);
+    let transactions = [I2cTrans::write(DEV_ADDR, vec![0]).with_error(nack)];
+    let mut expander = Pcf8574::new(I2cMock::new(&transactions), SlaveAddr::default());
+    let error = expander.set(0).unwrap_err();
+    assert_eq!(Some(nack), error.i2c_kind());
+    expander.destroy().done();
+}
+
+#[test]
+fn other_errors_have_no_i2c_error_kind() {
+    let error: Error<ErrorKind> = Error::InvalidInputData;
+    assert_eq!(None, error.i2c_kind());
+}
+
+#[test]
+fn can_display_errors() {
+    let error: Error<ErrorKind> = Error::I2C(ErrorKind::Bus);
+    assert_eq!("I²C bus error: Bus", format!("{}", error));
+    let error: Error<ErrorKind> = Error::OutputMismatch(0x0102);
+    assert_eq!(
+        "output pins set high read back low: 0x0102",
+        format!("{}", error)
+    );
+}
+
+#[test]
+fn implements_error_trait() {
+    let error: Error<ErrorKind> = Error::CouldNotAcquireDevice;
+    let error: &dyn core::error::Error = &error;
+    assert_eq!("could not acquire device", format!("{}", error));
+}
@@ -0,0 +1,168 @@
+use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTrans};
+use pcf857x::gesture::{Event, EventQueue, GestureDetector, Timings};
+use pcf857x::{Pcf8574, PinFlag, SlaveAddr};
+
+const DEV_ADDR: u8 = 0b010_0000;
+
+fn new() -> GestureDetector<8> {
+    let timings = Timings {
+        long_press: 100,
+        repeat_interval: 20,
+        double_click: 30,
+        chord_window: 10,
+    };
+    let mut detector = GestureDetector::new(PinFlag::P0 | PinFlag::P1 | PinFlag::P2, timings);
+    detector.update(0, 0);
+    detector
+}
+
+fn events(detector: &mut GestureDetector<8>) -> Vec<Event> {
+    core::iter::from_fn(|| detector.next_event()).collect()
+}
+
+#[test]
+fn first_snapshot_produces_no_events() {
+    let mut detector: GestureDetector<8> = GestureDetector::new(PinFlag::P0, Timings::default());
+    detector.update(0b1, 0);
+    assert_eq!(None, detector.next_event());
+    assert_eq!(1, detector.held());
+}
+
+#[test]
+fn pin_held_at_first_snapshot_produces_no_long_press_or_repeat() {
+    let mut detector: GestureDetector<8> = GestureDetector::new(PinFlag::P0, Timings::default());
+    detector.update(0b1, 0);
+    detector.update(0b1, 10);
+    detector.update(0b1, 1500);
+    detector.update(0b1, 2000);
+    assert_eq!(None, detector.next_event());
+    detector.update(0, 2010);
+    detector.update(0b1, 2020);
+    detector.update(0b1, 3020);
+    assert_eq!(
+        vec![
+            Event::Released(PinFlag::P0),
+            Event::Pressed(PinFlag::P0),
+            Event::LongPress(PinFlag::P0)
+        ],
+        events(&mut detector)
+    );
+}
+
+#[test]
+fn reports_press_and_release() {
+    let mut detector = new();
+    detector.update(0b1, 10);
+    detector.update(0b1000_0000, 20);
+    assert_eq!(
+        vec![Event::Pressed(PinFlag::P0), Event::Released(PinFlag::P0)],
+        events(&mut detector)
+    );
+}
+
+#[test]
+fn reports_long_press_and_repeat() {
+    let mut detector = new();
+    detector.update(0b10, 0);
+    detector.update(0b10, 99);
+    detector.update(0b10, 100);
+    detector.update(0b10, 119);
+    detector.update(0b10, 120);
+    detector.update(0, 125);
+    assert_eq!(
+        vec![
+            Event::Pressed(PinFlag::P1),
+            Event::LongPress(PinFlag::P1),
+            Event::Repeat(PinFlag::P1),
+            Event::Released(PinFlag::P1)
+        ],
+        events(&mut detector)
+    );
+    // a release after a long-press does not count as click
+    detector.update(0b10, 130);
+    assert_eq!(vec![Event::Pressed(PinFlag::P1)], events(&mut detector));
+}
+
+#[test]
+fn reports_double_click() {
+    let mut detector = new();
+    detector.update(0b1, 0);
+    detector.update(0, 10);
+    detector.update(0b1, 40);
+    detector.update(0, 50);
+    detector.update(0b1, 60);
+    assert_eq!(
+        vec![
+            Event::Pressed(PinFlag::P0),
+            Event::Released(PinFlag::P0),
+            Event::Pressed(PinFlag::P0),
+            Event::DoubleClick(PinFlag::P0),
+            Event::Released(PinFlag::P0),
+            Event::Pressed(PinFlag::P0),
+        ],
+        events(&mut detector)
+    );
+}
+
+#[test]
+fn slow_second_click_is_not_double_click() {
+    let mut detector = new();
+    detector.update(0b1, 0);
+    detector.update(0, 10);
+    detector.update(0b1, 41);
+    assert!(!events(&mut detector).contains(&Event::DoubleClick(PinFlag::P0)));
+}
+
+#[test]
+fn reports_chord() {
+    let mut detector = new();
+    detector.update(0b001, 0);
+    detector.update(0b101, 5);
+    detector.update(0b101, 6);
+    assert_eq!(
+        vec![
+            Event::Pressed(PinFlag::P0),
+            Event::Pressed(PinFlag::P2),
+            Event::Chord(PinFlag::P0 | PinFlag::P2)
+        ],
+        events(&mut detector)
+    );
+}
+
+#[test]
+fn late_second_press_is_not_chord() {
+    let mut detector = new();
+    detector.update(0b001, 0);
+    detector.update(0b011, 11);
+    assert!(!events(&mut detector).contains(&Event::Chord(PinFlag::P0 | PinFlag::P1)));
+}
+
+#[test]
+fn full_queue_drops_events() {
+    let mut queue: EventQueue<2> = EventQueue::new();
+    queue.push(Event::Pressed(PinFlag::P0));
+    queue.push(Event::Pressed(PinFlag::P1));
+    queue.push(Event::Pressed(PinFlag::P2));
+    assert_eq!(2, queue.len());
+    assert!(queue.take_overflow());
+    assert!(!queue.take_overflow());
+    assert_eq!(Some(Event::Pressed(PinFlag::P0)), queue.pop());
+    assert_eq!(Some(Event::Pressed(PinFlag::P1)), queue.pop());
+    assert_eq!(None, queue.pop());
+}
+
+#[test]
+fn can_poll_device() {
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, vec![0b1]),
+        I2cTrans::read(DEV_ADDR, vec![0b0]),
+        I2cTrans::write(DEV_ADDR, vec![0b1]),
+        I2cTrans::read(DEV_ADDR, vec![0b1]),
+    ];
+    let mut expander = Pcf8574::new(I2cMock::new(&transactions), SlaveAddr::default());
+    let mut detector: GestureDetector<4> = GestureDetector::new(PinFlag::P0, Timings::default());
+    detector.poll(&mut expander, 0).unwrap();
+    detector.poll(&mut expander, 1).unwrap();
+    assert_eq!(Some(Event::Pressed(PinFlag::P0)), detector.next_event());
+    expander.destroy().done();
+}
@@ -0,0 +1,255 @@
+use embedded_hal::i2c::{Error as _, ErrorKind, I2c, NoAcknowledgeSource};
+use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTrans};
+use pcf857x::i2c::{BitBangI2c, I2cError, Pins};
+use pcf857x::{Pcf8574, PinFlag, SlaveAddr};
+
+const DEV_ADDR: u8 = 0b010_0000;
+const SCL: u8 = 0b01;
+const SDA: u8 = 0b10;
+
+/// Expected expander transactions for a bit-banged bus on P0 (SCL) and P1 (SDA)
+struct Expect {
+    transactions: Vec<I2cTrans>,
+    pending: Vec<u8>,
+    last: u8,
+    stretch: bool,
+}
+
+impl Expect {
+    fn new(stretch: bool) -> Self {
+        Expect {
+            transactions: Vec::new(),
+            pending: Vec::new(),
+            last: 0,
+            stretch,
+        }
+    }
+
+    fn drive(&mut self, scl: bool, sda: bool) {
+        let word = if scl { SCL } else { 0 } | if sda { SDA } else { 0 };
+        self.pending.push(word);
+    }
+
+    fn flush(&mut self) {
+        if let Some(last) = self.pending.last() {
+            self.last = *last;
+            let data = core::mem::take(&mut self.pending);
+            self.transactions.push(I2cTrans::write(DEV_ADDR, data));
+        }
+    }
+
+    fn get(&mut self, mask: u8, value: u8) {
+        self.flush();
+        self.transactions
+            .push(I2cTrans::write(DEV_ADDR, vec![mask | self.last]));
+        self.transactions
+            .push(I2cTrans::read(DEV_ADDR, vec![value]));
+    }
+
+    fn clock_high(&mut self, sda: bool) {
+        self.drive(true, sda);
+        if self.stretch {
+            self.get(SCL, SCL);
+        }
+    }
+
+    fn write_bit(&mut self, bit: bool) {
+        self.drive(false, bit);
+        self.clock_high(bit);
+        self.drive(false, bit);
+    }
+
+    fn read_bit(&mut self, bit: bool) {
+        self.drive(false, true);
+        self.clock_high(true);
+        self.get(SDA, SCL | if bit { SDA } else { 0 });
+        self.drive(false, true);
+    }
+
+    fn start(&mut self) {
+        self.drive(true, true);
+        self.get(SCL | SDA, SCL | SDA);
+        self.drive(true, false);
+        self.drive(false, false);
+    }
+
+    fn repeated_start(&mut self) {
+        self.drive(false, true);
+        self.clock_high(true);
+        self.drive(true, false);
+        self.drive(false, false);
+    }
+
+    fn write_byte(&mut self, byte: u8, ack: bool) {
+        for bit in (0..8).rev() {
+            self.write_bit(byte & (1 << bit) != 0);
+        }
+        self.read_bit(!ack);
+    }
+
+    fn read_byte(&mut self, byte: u8, ack: bool) {
+        for bit in (0..8).rev() {
+            self.read_bit(byte & (1 << bit) != 0);
+        }
+        self.write_bit(!ack);
+    }
+
+    fn stop(&mut self) {
+        self.drive(false, false);
+        self.clock_high(false);
+        self.drive(true, true);
+        self.flush();
+    }
+
+    fn bus(&self) -> BitBangI2c<Pcf8574<I2cMock>> {
+        let expander = Pcf8574::new(I2cMock::new(&self.transactions), SlaveAddr::default());
+        let mut bus = BitBangI2c::new(
+            expander,
+            Pins {
+                scl: PinFlag::P0,
+                sda: PinFlag::P1,
+            },
+        );
+        if !self.stretch {
+            bus.set_clock_stretch_reads(None);
+        }
+        bus
+    }
+}
+
+#[test]
+fn write_without_clock_stretching_is_batched() {
+    let mut expect = Expect::new(false);
+    expect.start();
+    expect.write_byte(0x48 << 1, true);
+    expect.write_byte(0xAB, true);
+    expect.stop();
+    // start check, address byte, data byte, stop
+    assert_eq!(3 + 3 + 3 + 1, expect.transactions.len());
+    let mut bus = expect.bus();
+    bus.write(0x48, &[0xAB]).unwrap();
+    bus.destroy().destroy().done();
+}
+
+#[test]
+fn write_read_with_clock_stretching() {
+    let mut expect = Expect::new(true);
+    expect.start();
+    expect.write_byte(0x48 << 1, true);
+    expect.write_byte(0x01, true);
+    expect.repeated_start();
+    expect.write_byte(0x48 << 1 | 1, true);
+    expect.read_byte(0x12, true);
+    expect.read_byte(0x34, false);
+    expect.stop();
+    let mut bus = expect.bus();
+    let mut data = [0; 2];
+    bus.write_read(0x48, &[0x01], &mut data).unwrap();
+    assert_eq!([0x12, 0x34], data);
+    bus.destroy().destroy().done();
+}
+
+#[test]
+fn lines_use_physical_levels_when_inverted() {
+    let mut expect = Expect::new(true);
+    expect.start();
+    expect.write_byte(0x48 << 1 | 1, true);
+    expect.read_byte(0x5A, false);
+    expect.stop();
+    let mut expander = Pcf8574::new(I2cMock::new(&expect.transactions), SlaveAddr::default());
+    expander.set_inversion_mask(SCL | SDA);
+    let pins = Pins {
+        scl: PinFlag::P0,
+        sda: PinFlag::P1,
+    };
+    let mut bus = BitBangI2c::new(expander, pins);
+    let mut data = [0];
+    bus.read(0x48, &mut data).unwrap();
+    assert_eq!([0x5A], data);
+    bus.destroy().destroy().done();
+}
+
+#[test]
+fn waits_for_clock_stretching() {
+    let mut expect = Expect::new(true);
+    expect.start();
+    // the target stretches the clock on the first bit
+    expect.drive(false, false);
+    expect.drive(true, false);
+    expect.get(SCL, 0);
+    expect.get(SCL, 0);
+    expect.get(SCL, SCL);
+    expect.drive(false, false);
+    for _ in 0..6 {
+        expect.write_bit(true);
+    }
+    expect.write_bit(false);
+    expect.read_bit(false);
+    expect.stop();
+    let mut bus = expect.bus();
+    bus.write(0x3F, &[]).unwrap();
+    bus.destroy().destroy().done();
+}
+
+#[test]
+fn clock_stretch_timeout() {
+    let mut expect = Expect::new(true);
+    expect.start();
+    expect.drive(false, false);
+    expect.drive(true, false);
+    expect.get(SCL, 0);
+    expect.get(SCL, 0);
+    // stop, best effort
+    expect.drive(false, false);
+    expect.drive(true, false);
+    expect.get(SCL, SCL);
+    expect.drive(true, true);
+    expect.flush();
+    let mut bus = expect.bus();
+    bus.set_clock_stretch_reads(Some(2));
+    match bus.write(0x00, &[]) {
+        Err(I2cError::ClockStretchTimeout) => (),
+        _ => panic!(),
+    }
+    bus.destroy().destroy().done();
+}
+
+#[test]
+fn data_nack() {
+    let mut expect = Expect::new(false);
+    expect.start();
+    expect.write_byte(0x20 << 1, true);
+    expect.write_byte(0x55, false);
+    expect.stop();
+    let mut bus = expect.bus();
+    let error = bus.write(0x20, &[0x55, 0x66]).unwrap_err();
+    assert_eq!(
+        ErrorKind::NoAcknowledge(NoAcknowledgeSource::Data),
+        error.kind()
+    );
+    bus.destroy().destroy().done();
+}
+
+#[test]
+fn bus_busy() {
+    let mut expect = Expect::new(false);
+    expect.drive(true, true);
+    expect.get(SCL | SDA, SCL);
+    expect.stop();
+    let mut bus = expect.bus();
+    let error = bus.read(0x20, &mut [0]).unwrap_err();
+    assert_eq!(ErrorKind::Bus, error.kind());
+    bus.destroy().destroy().done();
+}
+
+#[test]
+fn invalid_address() {
+    let mut expect = Expect::new(false);
+    expect.stop();
+    let mut bus = expect.bus();
+    match bus.write(0x80, &[]) {
+        Err(I2cError::Device(pcf857x::Error::InvalidInputData)) => (),
+        _ => panic!(),
+    }
+    bus.destroy().destroy().done();
+}
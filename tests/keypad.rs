@@ -0,0 +1,128 @@
+use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTrans};
+use pcf857x::keypad::{KeyEvent, Keypad};
+use pcf857x::{Pcf8574, PinFlag, SlaveAddr};
+
+const DEV_ADDR: u8 = 0b010_0000;
+
+type TestKeypad = Keypad<Pcf8574<I2cMock>, char, 2, 2>;
+
+fn new(transactions: &[I2cTrans]) -> TestKeypad {
+    let expander = Pcf8574::new(I2cMock::new(transactions), SlaveAddr::default());
+    let rows = [PinFlag::P0, PinFlag::P1];
+    let columns = [PinFlag::P2, PinFlag::P3];
+    Keypad::new(expander, rows, columns, [['1', '2'], ['3', '4']], 10)
+}
+
+// Scan transactions for the column levels read on each row.
+fn scan(base: u8, columns: [u8; 2]) -> Vec<I2cTrans> {
+    let idle = base | 0b1111;
+    vec![
+        I2cTrans::write(DEV_ADDR, vec![idle & !0b01]),
+        I2cTrans::write(DEV_ADDR, vec![idle & !0b01]),
+        I2cTrans::read(DEV_ADDR, vec![columns[0] | 0b0011]),
+        I2cTrans::write(DEV_ADDR, vec![idle & !0b10]),
+        I2cTrans::write(DEV_ADDR, vec![idle & !0b10]),
+        I2cTrans::read(DEV_ADDR, vec![columns[1] | 0b0011]),
+        I2cTrans::write(DEV_ADDR, vec![idle]),
+    ]
+}
+
+#[test]
+fn no_key_pressed_produces_no_events() {
+    let mut keypad = new(&scan(0, [0b1100, 0b1100]));
+    keypad.scan(0).unwrap();
+    assert_eq!(None, keypad.next_event());
+    keypad.destroy().destroy().done();
+}
+
+#[test]
+fn reports_pressed_key_after_debounce_time() {
+    let transactions = [scan(0, [0b1100, 0b0100]), scan(0, [0b1100, 0b0100])].concat();
+    let mut keypad = new(&transactions);
+    keypad.scan(5).unwrap();
+    assert_eq!(None, keypad.next_event());
+    keypad.scan(15).unwrap();
+    assert_eq!(Some(KeyEvent::Pressed('4')), keypad.next_event());
+    assert_eq!(None, keypad.next_event());
+    assert_eq!(vec!['4'], keypad.pressed_keys().collect::<Vec<_>>());
+    keypad.destroy().destroy().done();
+}
+
+#[test]
+fn reports_released_key() {
+    let transactions = [
+        scan(0, [0b1000, 0b1100]),
+        scan(0, [0b1000, 0b1100]),
+        scan(0, [0b1100, 0b1100]),
+        scan(0, [0b1100, 0b1100]),
+    ]
+    .concat();
+    let mut keypad = new(&transactions);
+    keypad.scan(0).unwrap();
+    keypad.scan(10).unwrap();
+    assert_eq!(Some(KeyEvent::Pressed('1')), keypad.next_event());
+    keypad.scan(20).unwrap();
+    assert_eq!(None, keypad.next_event());
+    keypad.scan(30).unwrap();
+    assert_eq!(Some(KeyEvent::Released('1')), keypad.next_event());
+    keypad.destroy().destroy().done();
+}
+
+#[test]
+fn conserves_other_pins() {
+    let transactions = [
+        vec![I2cTrans::write(DEV_ADDR, vec![0b1000_0000])],
+        scan(0b1000_0000, [0b1100, 0b1100]),
+    ]
+    .concat();
+    let mut expander = Pcf8574::new(I2cMock::new(&transactions), SlaveAddr::default());
+    expander.set(0b1000_0000).unwrap();
+    let rows = [PinFlag::P0, PinFlag::P1];
+    let columns = [PinFlag::P2, PinFlag::P3];
+    let mut keypad = Keypad::new(&mut expander, rows, columns, [[1, 2], [3, 4]], 10);
+    keypad.scan(0).unwrap();
+    expander.destroy().done();
+}
+
+#[test]
+fn ignores_ghosting_matrix() {
+    let transactions = [scan(0, [0b0000, 0b0000]), scan(0, [0b0000, 0b0000])].concat();
+    let mut keypad = new(&transactions);
+    keypad.scan(10).unwrap();
+    assert!(keypad.is_ghosting());
+    assert_eq!(None, keypad.next_event());
+    keypad.scan(30).unwrap();
+    assert!(keypad.is_ghosting());
+    assert_eq!(None, keypad.next_event());
+    keypad.destroy().destroy().done();
+}
+
+#[test]
+fn can_detect_any_key_in_idle_mode() {
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, vec![0b1100]),
+        I2cTrans::write(DEV_ADDR, vec![0b1100]),
+        I2cTrans::read(DEV_ADDR, vec![0b1100]),
+        I2cTrans::write(DEV_ADDR, vec![0b1100]),
+        I2cTrans::read(DEV_ADDR, vec![0b0100]),
+    ];
+    let mut keypad = new(&transactions);
+    keypad.idle().unwrap();
+    assert!(!keypad.any_key_pressed().unwrap());
+    assert!(keypad.any_key_pressed().unwrap());
+    keypad.destroy().destroy().done();
+}
+
+#[test]
+fn scans_with_physical_levels_on_inverted_pins() {
+    let transactions = [scan(0, [0b1100, 0b0100]), scan(0, [0b1100, 0b0100])].concat();
+    let mut expander = Pcf8574::new(I2cMock::new(&transactions), SlaveAddr::default());
+    expander.set_inversion_mask(0b1111);
+    let rows = [PinFlag::P0, PinFlag::P1];
+    let columns = [PinFlag::P2, PinFlag::P3];
+    let mut keypad = Keypad::new(expander, rows, columns, [['1', '2'], ['3', '4']], 10);
+    keypad.scan(5).unwrap();
+    keypad.scan(15).unwrap();
+    assert_eq!(Some(KeyEvent::Pressed('4')), keypad.next_event());
+    keypad.destroy().destroy().done();
+}
@@ -0,0 +1,201 @@
+use embedded_hal_mock::eh1::delay::NoopDelay;
+use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTrans};
+use pcf857x::lcd::{FourBitBus, FourBitPins, Lcd};
+use pcf857x::{Error, Pcf8574, SlaveAddr};
+mod base;
+
+const DEV_ADDR: u8 = 0b010_0000;
+const RS: u8 = 0x01;
+const E: u8 = 0x04;
+const BL: u8 = 0x08;
+
+fn new(transactions: &[I2cTrans]) -> Lcd<FourBitBus<Pcf8574<I2cMock>>> {
+    let expander = Pcf8574::new(I2cMock::new(transactions), SlaveAddr::default());
+    Lcd::new(FourBitBus::new(expander, FourBitPins::default()), 16, 2)
+}
+
+fn destroy(lcd: Lcd<FourBitBus<Pcf8574<I2cMock>>>) {
+    lcd.destroy().destroy().destroy().done();
+}
+
+// Words strobing a byte through D4-D7 on P4-P7
+fn strobes(control: u8, byte: u8) -> Vec<u8> {
+    let high = (byte & 0xF0) | control;
+    let low = (byte << 4) | control;
+    vec![high | E, high, low | E, low]
+}
+
+#[test]
+fn can_init() {
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, vec![0x30 | E, 0x30]),
+        I2cTrans::write(DEV_ADDR, vec![0x30 | E, 0x30]),
+        I2cTrans::write(DEV_ADDR, vec![0x30 | E, 0x30]),
+        I2cTrans::write(DEV_ADDR, vec![0x20 | E, 0x20]),
+        I2cTrans::write(
+            DEV_ADDR,
+            [
+                strobes(0, 0x28),
+                strobes(0, 0x08),
+                strobes(0, 0x06),
+                strobes(0, 0x0C),
+            ]
+            .concat(),
+        ),
+        I2cTrans::write(DEV_ADDR, strobes(0, 0x01)),
+    ];
+    let mut lcd = new(&transactions);
+    lcd.init(&mut NoopDelay::new()).unwrap();
+    destroy(lcd);
+}
+
+#[test]
+fn can_write_text_in_one_transfer() {
+    let transactions = [I2cTrans::write(
+        DEV_ADDR,
+        [strobes(RS, b'H'), strobes(RS, b'i')].concat(),
+    )];
+    let mut lcd = new(&transactions);
+    lcd.write_str("Hi").unwrap();
+    destroy(lcd);
+}
+
+#[test]
+fn long_text_is_split_in_chunks() {
+    let text = b"0123456789ABCDEFG";
+    let words: Vec<u8> = text.iter().flat_map(|c| strobes(RS, *c)).collect();
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, words[..64].to_vec()),
+        I2cTrans::write(DEV_ADDR, words[64..].to_vec()),
+    ];
+    let mut lcd = new(&transactions);
+    lcd.write_bytes(text).unwrap();
+    destroy(lcd);
+}
+
+#[test]
+fn backlight_is_kept_when_writing() {
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, vec![BL]),
+        I2cTrans::write(DEV_ADDR, strobes(BL, 0xC3)),
+    ];
+    let mut lcd = new(&transactions);
+    lcd.set_backlight(true).unwrap();
+    lcd.set_cursor(3, 1).unwrap();
+    destroy(lcd);
+}
+
+#[test]
+fn cursor_outside_of_display_returns_error() {
+    let mut lcd = new(&[]);
+    expect_err!(lcd.set_cursor(16, 0), InvalidInputData);
+    expect_err!(lcd.set_cursor(0, 2), InvalidInputData);
+    destroy(lcd);
+}
+
+#[test]
+fn can_create_custom_char() {
+    let pattern = [0, 0x0A, 0x1F, 0x1F, 0x0E, 0x04, 0, 0];
+    let data: Vec<u8> = pattern.iter().flat_map(|row| strobes(RS, *row)).collect();
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, strobes(0, 0x48)),
+        I2cTrans::write(DEV_ADDR, data),
+    ];
+    let mut lcd = new(&transactions);
+    lcd.create_char(1, pattern).unwrap();
+    expect_err!(lcd.create_char(8, pattern), InvalidInputData);
+    destroy(lcd);
+}
+
+#[test]
+fn can_show_blinking_cursor() {
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, strobes(0, 0x0E)),
+        I2cTrans::write(DEV_ADDR, strobes(0, 0x0F)),
+        I2cTrans::write(DEV_ADDR, strobes(0, 0x0B)),
+    ];
+    let mut lcd = new(&transactions);
+    lcd.set_cursor_visible(true).unwrap();
+    lcd.set_cursor_blink(true).unwrap();
+    lcd.set_display(false).unwrap();
+    destroy(lcd);
+}
+
+mod eight_bit {
+    use super::*;
+    use pcf857x::lcd::{EightBitBus, EightBitPins};
+    use pcf857x::Pcf8575;
+
+    const RS: u8 = 0x01;
+    const E: u8 = 0x04;
+    const BL: u8 = 0x08;
+
+    fn new(transactions: &[I2cTrans]) -> Lcd<EightBitBus<Pcf8575<I2cMock>>> {
+        let expander = Pcf8575::new(I2cMock::new(transactions), SlaveAddr::default());
+        Lcd::new(EightBitBus::new(expander, EightBitPins::default()), 20, 4)
+    }
+
+    fn destroy(lcd: Lcd<EightBitBus<Pcf8575<I2cMock>>>) {
+        lcd.destroy().destroy().destroy().done();
+    }
+
+    fn strobe(control: u8, byte: u8) -> Vec<u8> {
+        vec![byte, control | E, byte, control]
+    }
+
+    #[test]
+    fn can_init() {
+        let transactions = [
+            I2cTrans::write(DEV_ADDR, strobe(0, 0x30)),
+            I2cTrans::write(DEV_ADDR, strobe(0, 0x30)),
+            I2cTrans::write(DEV_ADDR, strobe(0, 0x30)),
+            I2cTrans::write(
+                DEV_ADDR,
+                [
+                    strobe(0, 0x38),
+                    strobe(0, 0x08),
+                    strobe(0, 0x06),
+                    strobe(0, 0x0C),
+                ]
+                .concat(),
+            ),
+            I2cTrans::write(DEV_ADDR, strobe(0, 0x01)),
+        ];
+        let mut lcd = new(&transactions);
+        lcd.init(&mut NoopDelay::new()).unwrap();
+        destroy(lcd);
+    }
+
+    #[test]
+    fn can_write_text_in_one_transfer() {
+        let transactions = [I2cTrans::write(
+            DEV_ADDR,
+            [strobe(RS, b'O'), strobe(RS, b'K')].concat(),
+        )];
+        let mut lcd = new(&transactions);
+        lcd.write_str("OK").unwrap();
+        destroy(lcd);
+    }
+
+    #[test]
+    fn can_set_cursor_on_fourth_row_with_backlight() {
+        let transactions = [
+            I2cTrans::write(DEV_ADDR, vec![0, BL]),
+            I2cTrans::write(DEV_ADDR, strobe(BL, 0x80 | (0x54 + 2))),
+        ];
+        let mut lcd = new(&transactions);
+        lcd.set_backlight(true).unwrap();
+        lcd.set_cursor(2, 3).unwrap();
+        destroy(lcd);
+    }
+
+    #[test]
+    fn can_use_borrowed_device() {
+        let transactions = [I2cTrans::write(DEV_ADDR, strobe(0, 0x02))];
+        let mut expander = Pcf8575::new(I2cMock::new(&transactions), SlaveAddr::default());
+        let bus = EightBitBus::new(&mut expander, EightBitPins::default());
+        let mut lcd = Lcd::new(bus, 20, 4);
+        lcd.home(&mut NoopDelay::new()).unwrap();
+        expander.destroy().done();
+    }
+}
@@ -0,0 +1,72 @@
+use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTrans};
+use pcf857x::led_matrix::{LedMatrix, Polarity};
+use pcf857x::{Pcf8575, SlaveAddr};
+
+const DEV_ADDR: u8 = 0b010_0000;
+
+fn new(transactions: &[I2cTrans]) -> LedMatrix<Pcf8575<I2cMock>> {
+    let expander = Pcf8575::new(I2cMock::new(transactions), SlaveAddr::default());
+    LedMatrix::new(expander, Polarity::ActiveHigh, Polarity::ActiveLow)
+}
+
+#[test]
+fn can_set_pixels() {
+    let mut matrix = new(&[]);
+    matrix.set_pixel(1, 2, true);
+    matrix.set_pixel(7, 7, true);
+    matrix.set_pixel(8, 0, true);
+    matrix.set_pixel(7, 7, false);
+    assert!(matrix.pixel(1, 2));
+    assert!(!matrix.pixel(7, 7));
+    assert!(!matrix.pixel(8, 0));
+    assert_eq!(&[0, 0, 0b10, 0, 0, 0, 0, 0], matrix.framebuffer());
+    matrix.destroy().destroy().done();
+}
+
+#[test]
+fn tick_scans_one_row_per_set() {
+    let mut transactions: Vec<I2cTrans> = (0..8)
+        .map(|row| I2cTrans::write(DEV_ADDR, vec![1 << row, 0xFF]))
+        .collect();
+    transactions[1] = I2cTrans::write(DEV_ADDR, vec![0b10, !0b1000_0001]);
+    transactions.push(I2cTrans::write(DEV_ADDR, vec![1, 0xFF]));
+    let mut matrix = new(&transactions);
+    matrix.framebuffer_mut()[1] = 0b1000_0001;
+    for _ in 0..9 {
+        matrix.tick().unwrap();
+    }
+    matrix.destroy().destroy().done();
+}
+
+#[test]
+fn can_blank() {
+    let transactions = [I2cTrans::write(DEV_ADDR, vec![0, 0xFF])];
+    let mut matrix = new(&transactions);
+    matrix.blank().unwrap();
+    matrix.destroy().destroy().done();
+}
+
+#[cfg(feature = "graphics")]
+mod graphics {
+    use super::*;
+    use embedded_graphics_core::{
+        draw_target::DrawTarget, geometry::Point, pixelcolor::BinaryColor, Pixel,
+    };
+
+    #[test]
+    fn can_draw_pixels() {
+        let mut matrix = new(&[]);
+        matrix
+            .draw_iter([
+                Pixel(Point::new(0, 0), BinaryColor::On),
+                Pixel(Point::new(3, 7), BinaryColor::On),
+                Pixel(Point::new(-1, 0), BinaryColor::On),
+                Pixel(Point::new(0, 8), BinaryColor::On),
+            ])
+            .unwrap();
+        assert_eq!(&[1, 0, 0, 0, 0, 0, 0, 0b1000], matrix.framebuffer());
+        DrawTarget::clear(&mut matrix, BinaryColor::On).unwrap();
+        assert_eq!(&[0xFF; 8], matrix.framebuffer());
+        matrix.destroy().destroy().done();
+    }
+}
@@ -0,0 +1,142 @@
+use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTrans};
+use pcf857x::parallel::{ParallelBus, ParallelError, Pins};
+use pcf857x::{Pcf8575, PinFlag, SlaveAddr};
+
+const DEV_ADDR: u8 = 0b010_0000;
+
+fn new(transactions: &[I2cTrans], pins: Pins) -> ParallelBus<Pcf8575<I2cMock>> {
+    let expander = Pcf8575::new(I2cMock::new(transactions), SlaveAddr::default());
+    ParallelBus::new(expander, pins)
+}
+
+fn strobe(byte: u8, control: u8, strobe: u8) -> Vec<u8> {
+    vec![byte, control, byte, control & !strobe, byte, control]
+}
+
+fn get(last_set: [u8; 2], mask: u8, upper: u8) -> [I2cTrans; 2] {
+    [
+        I2cTrans::write(DEV_ADDR, vec![last_set[0], last_set[1] | mask]),
+        I2cTrans::read(DEV_ADDR, vec![0, upper]),
+    ]
+}
+
+const NO_HANDSHAKE: Pins = Pins {
+    strobe: PinFlag::P10,
+    busy: None,
+    ack: None,
+};
+
+#[test]
+fn without_handshake_is_batched() {
+    let mut data = strobe(b'A', 0b01, 0b01);
+    data.extend(strobe(b'B', 0b01, 0b01));
+    let mut bus = new(&[I2cTrans::write(DEV_ADDR, data)], NO_HANDSHAKE);
+    bus.write(b"AB").unwrap();
+    bus.destroy().destroy().done();
+}
+
+fn busy_transactions() -> Vec<I2cTrans> {
+    let mut transactions = Vec::new();
+    transactions.extend(get([0, 0], 0b10, 0b10));
+    transactions.extend(get([0, 0], 0b10, 0));
+    transactions.push(I2cTrans::write(DEV_ADDR, strobe(b'A', 0b11, 0b01)));
+    transactions.extend(get([b'A', 0b11], 0b10, 0));
+    transactions.push(I2cTrans::write(DEV_ADDR, strobe(b'B', 0b11, 0b01)));
+    transactions
+}
+
+#[test]
+fn waits_while_busy() {
+    let mut bus = new(&busy_transactions(), Pins::default());
+    bus.write(b"AB").unwrap();
+    bus.destroy().destroy().done();
+}
+
+#[test]
+fn busy_line_uses_physical_level_when_inverted() {
+    let transactions = busy_transactions();
+    let mut expander = Pcf8575::new(I2cMock::new(&transactions), SlaveAddr::default());
+    expander.set_inversion_mask(0x0200);
+    let mut bus = ParallelBus::new(expander, Pins::default());
+    bus.write(b"AB").unwrap();
+    bus.destroy().destroy().done();
+}
+
+#[test]
+fn busy_timeout() {
+    let mut transactions = Vec::new();
+    transactions.extend(get([0, 0], 0b10, 0b10));
+    transactions.extend(get([0, 0], 0b10, 0b10));
+    let mut bus = new(&transactions, Pins::default());
+    bus.set_max_polls(Some(2));
+    match bus.write(b"A") {
+        Err(ParallelError::Timeout) => (),
+        _ => panic!(),
+    }
+    bus.destroy().destroy().done();
+}
+
+#[test]
+fn waits_for_ack() {
+    let pins = Pins {
+        strobe: PinFlag::P10,
+        busy: None,
+        ack: Some(PinFlag::P12),
+    };
+    let mut transactions = vec![I2cTrans::write(DEV_ADDR, strobe(b'A', 0b101, 0b001))];
+    transactions.extend(get([b'A', 0b101], 0b100, 0b100));
+    transactions.extend(get([b'A', 0b101], 0b100, 0));
+    let mut bus = new(&transactions, pins);
+    bus.write(b"A").unwrap();
+    bus.destroy().destroy().done();
+}
+
+#[test]
+fn keeps_other_pins() {
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, vec![0, 0x80]),
+        I2cTrans::write(DEV_ADDR, strobe(0x55, 0x81, 0x01)),
+    ];
+    let mut expander = Pcf8575::new(I2cMock::new(&transactions), SlaveAddr::default());
+    expander.set(0x8000).unwrap();
+    let mut bus = ParallelBus::new(&mut expander, NO_HANDSHAKE);
+    bus.write(&[0x55]).unwrap();
+    expander.destroy().done();
+}
+
+#[cfg(feature = "io")]
+mod io {
+    use super::*;
+    use embedded_io::{Error as _, ErrorKind, Write};
+
+    #[test]
+    fn returns_bytes_written_before_timeout() {
+        let mut transactions = Vec::new();
+        transactions.extend(get([0, 0], 0b10, 0));
+        transactions.push(I2cTrans::write(DEV_ADDR, strobe(b'A', 0b11, 0b01)));
+        transactions.extend(get([b'A', 0b11], 0b10, 0b10));
+        let mut bus = new(&transactions, Pins::default());
+        bus.set_max_polls(Some(1));
+        assert_eq!(1, Write::write(&mut bus, b"AB").unwrap());
+        bus.destroy().destroy().done();
+    }
+
+    #[test]
+    fn timeout_error_kind() {
+        let transactions = get([0, 0], 0b10, 0b10);
+        let mut bus = new(&transactions, Pins::default());
+        bus.set_max_polls(Some(1));
+        let error = Write::write(&mut bus, b"A").unwrap_err();
+        assert_eq!(ErrorKind::TimedOut, error.kind());
+        bus.destroy().destroy().done();
+    }
+
+    #[test]
+    fn write_all() {
+        let mut data = strobe(b'O', 0b01, 0b01);
+        data.extend(strobe(b'K', 0b01, 0b01));
+        let mut bus = new(&[I2cTrans::write(DEV_ADDR, data)], NO_HANDSHAKE);
+        bus.write_all(b"OK").unwrap();
+        bus.destroy().destroy().done();
+    }
+}
@@ -1,7 +1,19 @@
+use embedded_hal_mock::eh1::delay::NoopDelay;
 use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTrans};
 use pcf857x::{Error, Pcf8574, Pcf8574a, PinFlag, SlaveAddr};
 mod base;
 
+fn all_pins() -> PinFlag {
+    PinFlag::P0
+        | PinFlag::P1
+        | PinFlag::P2
+        | PinFlag::P3
+        | PinFlag::P4
+        | PinFlag::P5
+        | PinFlag::P6
+        | PinFlag::P7
+}
+
 macro_rules! pcf8574_tests {
     ($device_name:ident, $test_mod_name:ident, $default_address:expr) => {
         mod $test_mod_name {
@@ -120,6 +132,229 @@ macro_rules! pcf8574_tests {
                 expect_err!(expander.read_array(mask, &mut data), InvalidInputData);
                 expander.destroy().done();
             }
+            #[test]
+            fn set_inverts_selected_pins() {
+                let transactions = [I2cTrans::write($default_address, vec![0b1010_0101])];
+                let mut expander = new(&transactions);
+                expander.set_inversion_mask(0b0000_1111);
+                expander.set(0b1010_1010).unwrap();
+                expander.destroy().done();
+            }
+
+            #[test]
+            fn get_inverts_selected_pins_and_conserves_physical_outputs() {
+                let transactions = [
+                    I2cTrans::write($default_address, vec![0b0000_1111]),
+                    I2cTrans::write($default_address, vec![0b1000_1111]),
+                    I2cTrans::read($default_address, vec![0b1000_1110]),
+                ];
+                let mut expander = new(&transactions);
+                expander.set_inversion_mask(0b0000_1111);
+                expander.set(0).unwrap();
+                let status = expander.get(PinFlag::P7).unwrap();
+                assert_eq!(0b1000_0001, status);
+                expander.destroy().done();
+            }
+
+            #[test]
+            fn write_array_inverts_selected_pins() {
+                let transactions = [
+                    I2cTrans::write($default_address, vec![0b1111_0000, 0b0000_1111]),
+                    I2cTrans::write($default_address, vec![0b0000_1111 | 128]),
+                    I2cTrans::read($default_address, vec![0xFF]),
+                ];
+                let mut expander = new(&transactions);
+                expander.set_inversion_mask(0b0000_1111);
+                expander.write_array(&[0b1111_1111, 0]).unwrap();
+                expander.get(PinFlag::P7).unwrap();
+                expander.destroy().done();
+            }
+
+            #[test]
+            fn read_array_inverts_selected_pins() {
+                let transactions = [
+                    I2cTrans::write($default_address, vec![1]),
+                    I2cTrans::read($default_address, vec![0xFF, 0x00]),
+                ];
+                let mut expander = new(&transactions);
+                expander.set_inversion_mask(0b1111_0000);
+                let mut data = [0; 2];
+                expander.read_array(PinFlag::P0, &mut data).unwrap();
+                assert_eq!([0x0F, 0xF0], data);
+                expander.destroy().done();
+            }
+
+            #[test]
+            fn pulse_restores_previous_state_of_selected_pins() {
+                let transactions = [
+                    I2cTrans::write($default_address, vec![0b1010_0101]),
+                    I2cTrans::write($default_address, vec![0b1010_1111]),
+                    I2cTrans::write($default_address, vec![0b1010_0101]),
+                ];
+                let mut expander = new(&transactions);
+                expander.set(0b1010_0101).unwrap();
+                let mask = PinFlag::P1 | PinFlag::P2 | PinFlag::P3;
+                expander
+                    .pulse(mask, true, 1000, &mut NoopDelay::new())
+                    .unwrap();
+                expander.destroy().done();
+            }
+
+            #[test]
+            fn pulse_low_inverted_pin_drives_it_high() {
+                let transactions = [
+                    I2cTrans::write($default_address, vec![0b0000_0000]),
+                    I2cTrans::write($default_address, vec![0b0000_0001]),
+                    I2cTrans::write($default_address, vec![0b0000_0000]),
+                ];
+                let mut expander = new(&transactions);
+                expander.set_inversion_mask(0b0000_0001);
+                expander.set(0b0000_0001).unwrap();
+                expander
+                    .pulse(PinFlag::P0, false, 1000, &mut NoopDelay::new())
+                    .unwrap();
+                expander.destroy().done();
+            }
+
+            #[test]
+            fn pulse_with_wrong_pin_flag_returns_error() {
+                let mut expander = new(&[]);
+                let mask = PinFlag::P0 | PinFlag::P17;
+                expect_err!(
+                    expander.pulse(mask, true, 1000, &mut NoopDelay::new()),
+                    InvalidInputData
+                );
+                expander.destroy().done();
+            }
+
+            #[test]
+            fn verify_accepts_matching_read_back() {
+                let transactions = [
+                    I2cTrans::write($default_address, vec![0b1010_0101]),
+                    I2cTrans::read($default_address, vec![0b1010_0101]),
+                ];
+                let mut expander = new(&transactions);
+                expander.set_verify(Some(all_pins())).unwrap();
+                expander.set(0b1010_0101).unwrap();
+                expander.destroy().done();
+            }
+
+            #[test]
+            fn verify_reports_pins_set_high_reading_low() {
+                let transactions = [
+                    I2cTrans::write($default_address, vec![0b1010_0101]),
+                    I2cTrans::read($default_address, vec![0b0010_0110]),
+                ];
+                let mut expander = new(&transactions);
+                expander.set_verify(Some(all_pins())).unwrap();
+                match expander.set(0b1010_0101) {
+                    Err(Error::OutputMismatch(0b1000_0001)) => (),
+                    _ => panic!(),
+                }
+                expander.destroy().done();
+            }
+
+            #[test]
+            fn verify_ignores_pins_outside_mask() {
+                let transactions = [
+                    I2cTrans::write($default_address, vec![0b1111_0101]),
+                    I2cTrans::read($default_address, vec![0b0000_0101]),
+                ];
+                let mut expander = new(&transactions);
+                expander
+                    .set_verify(Some(PinFlag::P0 | PinFlag::P1 | PinFlag::P2 | PinFlag::P3))
+                    .unwrap();
+                expander.set(0b1111_0101).unwrap();
+                expander.destroy().done();
+            }
+
+            #[test]
+            fn pulse_restores_pins_after_output_mismatch() {
+                let transactions = [
+                    I2cTrans::write($default_address, vec![0b0000_0000]),
+                    I2cTrans::write($default_address, vec![0b0000_0001]),
+                    I2cTrans::read($default_address, vec![0b0000_0000]),
+                    I2cTrans::write($default_address, vec![0b0000_0000]),
+                    I2cTrans::read($default_address, vec![0b0000_0000]),
+                ];
+                let mut expander = new(&transactions);
+                expander.set(0).unwrap();
+                expander.set_verify(Some(all_pins())).unwrap();
+                match expander.pulse(PinFlag::P0, true, 1000, &mut NoopDelay::new()) {
+                    Err(Error::OutputMismatch(0b0000_0001)) => (),
+                    _ => panic!(),
+                }
+                expander.destroy().done();
+            }
+
+            #[test]
+            fn verify_with_wrong_pin_flag_returns_error() {
+                let mut expander = new(&[]);
+                expect_err!(
+                    expander.set_verify(Some(PinFlag::P0 | PinFlag::P17)),
+                    InvalidInputData
+                );
+                expander.destroy().done();
+            }
+
+            #[test]
+            fn verify_checks_split_pin_writes() {
+                let transactions = [
+                    I2cTrans::write($default_address, vec![0b0000_0100]),
+                    I2cTrans::read($default_address, vec![0b0000_0000]),
+                ];
+                let mut expander = new(&transactions);
+                expander.set_verify(Some(all_pins())).unwrap();
+                {
+                    use pcf857x::OutputPin;
+                    let mut parts = expander.split();
+                    match parts.p2.set_high() {
+                        Err(Error::OutputMismatch(0b0000_0100)) => (),
+                        _ => panic!(),
+                    }
+                }
+                expander.destroy().done();
+            }
+
+            #[test]
+            fn can_find_stuck_low_pins_and_restore_status() {
+                let transactions = [
+                    I2cTrans::write($default_address, vec![0b1000_0001]),
+                    I2cTrans::write($default_address, vec![0b1000_1111]),
+                    I2cTrans::read($default_address, vec![0b1000_1011]),
+                    I2cTrans::write($default_address, vec![0b1000_0001]),
+                ];
+                let mut expander = new(&transactions);
+                expander.set(0b1000_0001).unwrap();
+                let mask = PinFlag::P0 | PinFlag::P1 | PinFlag::P2 | PinFlag::P3;
+                assert_eq!(0b0000_0100, expander.stuck_low_pins(mask).unwrap());
+                expander.destroy().done();
+            }
+
+            #[test]
+            fn stuck_low_pins_restores_status_after_read_error() {
+                let transactions = [
+                    I2cTrans::write($default_address, vec![0b1000_1111]),
+                    I2cTrans::read($default_address, vec![0])
+                        .with_error(embedded_hal::i2c::ErrorKind::Other),
+                    I2cTrans::write($default_address, vec![0b0000_0000]),
+                ];
+                let mut expander = new(&transactions);
+                let mask = PinFlag::P0 | PinFlag::P1 | PinFlag::P2 | PinFlag::P3 | PinFlag::P7;
+                match expander.stuck_low_pins(mask) {
+                    Err(Error::I2C(embedded_hal::i2c::ErrorKind::Other)) => (),
+                    _ => panic!(),
+                }
+                expander.destroy().done();
+            }
+
+            #[test]
+            fn stuck_low_pins_with_wrong_pin_flag_returns_error() {
+                let mut expander = new(&[]);
+                expect_err!(expander.stuck_low_pins(PinFlag::P17), InvalidInputData);
+                expander.destroy().done();
+            }
+
             pcf8574_pin_test!(p0, 1, $default_address);
             pcf8574_pin_test!(p1, 2, $default_address);
             pcf8574_pin_test!(p2, 4, $default_address);
@@ -166,6 +401,53 @@ macro_rules! pcf8574_pin_test {
                 expander.destroy().done();
             }
 
+            #[test]
+            fn can_split_and_pulse_low() {
+                let transactions = [
+                    I2cTrans::write($default_address, vec![0b1111_1111]),
+                    I2cTrans::write($default_address, vec![0b1111_1111 & !$value]),
+                    I2cTrans::write($default_address, vec![0b1111_1111]),
+                ];
+                let mut expander = new(&transactions);
+                expander.set(0b1111_1111).unwrap();
+                {
+                    let mut parts = expander.split();
+                    parts.$px.pulse(false, 1000, &mut NoopDelay::new()).unwrap();
+                }
+                expander.destroy().done();
+            }
+
+            #[test]
+            fn can_split_and_set_inverted_pin_high() {
+                let transactions = [
+                    I2cTrans::write($default_address, vec![0b1111_1111]),
+                    I2cTrans::write($default_address, vec![0b1111_1111 & !$value]),
+                ];
+                let mut expander = new(&transactions);
+                expander.set_inversion_mask($value);
+                expander.set(!$value).unwrap();
+                {
+                    let mut parts = expander.split();
+                    parts.$px.set_high().unwrap();
+                }
+                expander.destroy().done();
+            }
+
+            #[test]
+            fn can_split_and_get_inverted_pin_is_high() {
+                let transactions = [
+                    I2cTrans::write($default_address, vec![$value]),
+                    I2cTrans::read($default_address, vec![!$value]),
+                ];
+                let mut expander = new(&transactions);
+                expander.set_inversion_mask($value);
+                {
+                    let mut parts = expander.split();
+                    assert!(parts.$px.is_high().unwrap());
+                }
+                expander.destroy().done();
+            }
+
             #[test]
             fn can_split_and_get_is_high() {
                 let transactions = [
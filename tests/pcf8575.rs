@@ -1,9 +1,33 @@
+use embedded_hal_mock::eh1::delay::NoopDelay;
 use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTrans};
 use pcf857x::{Error, Pcf8575, PinFlag, SlaveAddr};
 mod base;
 
 const DEV_ADDR: u8 = 0b010_0000;
 
+fn low_pins() -> PinFlag {
+    PinFlag::P0
+        | PinFlag::P1
+        | PinFlag::P2
+        | PinFlag::P3
+        | PinFlag::P4
+        | PinFlag::P5
+        | PinFlag::P6
+        | PinFlag::P7
+}
+
+fn all_pins() -> PinFlag {
+    low_pins()
+        | PinFlag::P10
+        | PinFlag::P11
+        | PinFlag::P12
+        | PinFlag::P13
+        | PinFlag::P14
+        | PinFlag::P15
+        | PinFlag::P16
+        | PinFlag::P17
+}
+
 pub fn new(transactions: &[I2cTrans]) -> Pcf8575<I2cMock> {
     Pcf8575::new(I2cMock::new(transactions), SlaveAddr::default())
 }
@@ -120,6 +144,205 @@ fn reading_multiple_words_conserves_high_pins() {
     expander.destroy().done();
 }
 
+#[test]
+fn set_inverts_selected_pins() {
+    let transactions = [I2cTrans::write(DEV_ADDR, vec![0b1010_0101, 0b0101_1010])];
+    let mut expander = new(&transactions);
+    expander.set_inversion_mask(0xF00F);
+    expander.set(0b1010_1010_1010_1010).unwrap();
+    expander.destroy().done();
+}
+
+#[test]
+fn get_inverts_selected_pins_and_conserves_physical_outputs() {
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, vec![0x0F, 0x00]),
+        I2cTrans::write(DEV_ADDR, vec![0x0F, 0x80]),
+        I2cTrans::read(DEV_ADDR, vec![0x0F, 0x80]),
+    ];
+    let mut expander = new(&transactions);
+    expander.set_inversion_mask(0x000F);
+    expander.set(0).unwrap();
+    let status = expander.get(PinFlag::P17).unwrap();
+    assert_eq!(0x8000, status);
+    expander.destroy().done();
+}
+
+#[test]
+fn write_array_inverts_selected_pins() {
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, vec![0xF0, 0xFF, 0x0F, 0x00]),
+        I2cTrans::write(DEV_ADDR, vec![0x0F | 1, 0x00]),
+        I2cTrans::read(DEV_ADDR, vec![0xFF, 0xFF]),
+    ];
+    let mut expander = new(&transactions);
+    expander.set_inversion_mask(0x000F);
+    expander.write_array(&[0xFF, 0xFF, 0x00, 0x00]).unwrap();
+    expander.get(PinFlag::P0).unwrap();
+    expander.destroy().done();
+}
+
+#[test]
+fn write_array_with_inversion_is_sent_in_chunks() {
+    let data = [0; 34];
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, [0x01, 0x80].repeat(16)),
+        I2cTrans::write(DEV_ADDR, vec![0x01, 0x80]),
+    ];
+    let mut expander = new(&transactions);
+    expander.set_inversion_mask(0x8001);
+    expander.write_array(&data).unwrap();
+    expander.destroy().done();
+}
+
+#[test]
+fn read_array_inverts_selected_pins() {
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, vec![0x01, 0x00]),
+        I2cTrans::read(DEV_ADDR, vec![0xFF, 0xFF, 0x00, 0x00]),
+    ];
+    let mut expander = new(&transactions);
+    expander.set_inversion_mask(0xFF00);
+    let mut data = [0; 4];
+    expander.read_array(PinFlag::P0, &mut data).unwrap();
+    assert_eq!([0xFF, 0x00, 0x00, 0xFF], data);
+    expander.destroy().done();
+}
+
+#[test]
+fn pulse_restores_previous_state_of_selected_pins() {
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, vec![0x0F, 0xF0]),
+        I2cTrans::write(DEV_ADDR, vec![0x0E, 0xE0]),
+        I2cTrans::write(DEV_ADDR, vec![0x0F, 0xF0]),
+    ];
+    let mut expander = new(&transactions);
+    expander.set(0xF00F).unwrap();
+    let mask = PinFlag::P0 | PinFlag::P14;
+    expander
+        .pulse(mask, false, 1000, &mut NoopDelay::new())
+        .unwrap();
+    expander.destroy().done();
+}
+
+#[test]
+fn pulse_inverted_pins_drives_physical_level() {
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, vec![0x00, 0x80]),
+        I2cTrans::write(DEV_ADDR, vec![0x01, 0x00]),
+        I2cTrans::write(DEV_ADDR, vec![0x00, 0x80]),
+    ];
+    let mut expander = new(&transactions);
+    expander.set_inversion_mask(0x8000);
+    expander.set(0).unwrap();
+    let mask = PinFlag::P0 | PinFlag::P17;
+    expander
+        .pulse(mask, true, 1000, &mut NoopDelay::new())
+        .unwrap();
+    expander.destroy().done();
+}
+
+#[test]
+fn verify_accepts_matching_read_back() {
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, vec![0x0F, 0xF0]),
+        I2cTrans::read(DEV_ADDR, vec![0x0F, 0xF0]),
+    ];
+    let mut expander = new(&transactions);
+    expander.set_verify(Some(all_pins()));
+    expander.set(0xF00F).unwrap();
+    expander.destroy().done();
+}
+
+#[test]
+fn verify_reports_pins_set_high_reading_low() {
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, vec![0x0F, 0xF0]),
+        I2cTrans::read(DEV_ADDR, vec![0x0E, 0x70]),
+    ];
+    let mut expander = new(&transactions);
+    expander.set_verify(Some(all_pins()));
+    match expander.set(0xF00F) {
+        Err(Error::OutputMismatch(0x8001)) => (),
+        _ => panic!(),
+    }
+    expander.destroy().done();
+}
+
+#[test]
+fn verify_ignores_pins_outside_mask() {
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, vec![0x0F, 0xFF]),
+        I2cTrans::read(DEV_ADDR, vec![0x0F, 0x00]),
+    ];
+    let mut expander = new(&transactions);
+    expander.set_verify(Some(low_pins()));
+    expander.set(0xFF0F).unwrap();
+    expander.destroy().done();
+}
+
+#[test]
+fn pulse_restores_pins_after_output_mismatch() {
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, vec![0x00, 0x00]),
+        I2cTrans::write(DEV_ADDR, vec![0x00, 0x40]),
+        I2cTrans::read(DEV_ADDR, vec![0x00, 0x00]),
+        I2cTrans::write(DEV_ADDR, vec![0x00, 0x00]),
+        I2cTrans::read(DEV_ADDR, vec![0x00, 0x00]),
+    ];
+    let mut expander = new(&transactions);
+    expander.set(0).unwrap();
+    expander.set_verify(Some(all_pins()));
+    match expander.pulse(PinFlag::P16, true, 1000, &mut NoopDelay::new()) {
+        Err(Error::OutputMismatch(0x4000)) => (),
+        _ => panic!(),
+    }
+    expander.destroy().done();
+}
+
+#[test]
+fn can_find_stuck_low_pins_and_restore_status() {
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, vec![0x01, 0x00]),
+        I2cTrans::write(DEV_ADDR, vec![0x01, 0x03]),
+        I2cTrans::read(DEV_ADDR, vec![0x01, 0x01]),
+        I2cTrans::write(DEV_ADDR, vec![0x01, 0x00]),
+    ];
+    let mut expander = new(&transactions);
+    expander.set(0x0001).unwrap();
+    let mask = PinFlag::P10 | PinFlag::P11;
+    assert_eq!(0x0200, expander.stuck_low_pins(mask).unwrap());
+    expander.destroy().done();
+}
+
+#[test]
+fn stuck_low_pins_restores_status_after_read_error() {
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, vec![0x00, 0x03]),
+        I2cTrans::read(DEV_ADDR, vec![0, 0]).with_error(embedded_hal::i2c::ErrorKind::Other),
+        I2cTrans::write(DEV_ADDR, vec![0x00, 0x00]),
+    ];
+    let mut expander = new(&transactions);
+    match expander.stuck_low_pins(PinFlag::P10 | PinFlag::P11) {
+        Err(Error::I2C(embedded_hal::i2c::ErrorKind::Other)) => (),
+        _ => panic!(),
+    }
+    expander.destroy().done();
+}
+
+#[test]
+fn stuck_low_pins_does_not_restore_pins_already_high() {
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, vec![0x01, 0x00]),
+        I2cTrans::write(DEV_ADDR, vec![0x01, 0x00]),
+        I2cTrans::read(DEV_ADDR, vec![0x00, 0x00]),
+    ];
+    let mut expander = new(&transactions);
+    expander.set(0x0001).unwrap();
+    assert_eq!(0x0001, expander.stuck_low_pins(PinFlag::P0).unwrap());
+    expander.destroy().done();
+}
+
 macro_rules! pin_test {
     ($px:ident, $value:expr) => {
         mod $px {
@@ -158,6 +381,59 @@ macro_rules! pin_test {
                 expander.destroy().done();
             }
 
+            #[test]
+            fn can_split_and_pulse_low() {
+                let transactions = [
+                    I2cTrans::write(DEV_ADDR, vec![0b1111_1111, 0b1111_1111]),
+                    I2cTrans::write(
+                        DEV_ADDR,
+                        u16_to_u8_array(0b1111_1111_1111_1111 & !$value).to_vec(),
+                    ),
+                    I2cTrans::write(DEV_ADDR, vec![0b1111_1111, 0b1111_1111]),
+                ];
+                let mut expander = new(&transactions);
+                expander.set(0b1111_1111_1111_1111).unwrap();
+                {
+                    let mut parts = expander.split();
+                    parts.$px.pulse(false, 1000, &mut NoopDelay::new()).unwrap();
+                }
+                expander.destroy().done();
+            }
+
+            #[test]
+            fn can_split_and_set_inverted_pin_high() {
+                let transactions = [
+                    I2cTrans::write(DEV_ADDR, vec![0b1111_1111, 0b1111_1111]),
+                    I2cTrans::write(
+                        DEV_ADDR,
+                        u16_to_u8_array(0b1111_1111_1111_1111 & !$value).to_vec(),
+                    ),
+                ];
+                let mut expander = new(&transactions);
+                expander.set_inversion_mask($value);
+                expander.set(!$value).unwrap();
+                {
+                    let mut parts = expander.split();
+                    parts.$px.set_high().unwrap();
+                }
+                expander.destroy().done();
+            }
+
+            #[test]
+            fn can_split_and_get_inverted_pin_is_high() {
+                let transactions = [
+                    I2cTrans::write(DEV_ADDR, u16_to_u8_array($value).to_vec()),
+                    I2cTrans::read(DEV_ADDR, u16_to_u8_array(!$value).to_vec()),
+                ];
+                let mut expander = new(&transactions);
+                expander.set_inversion_mask($value);
+                {
+                    let mut parts = expander.split();
+                    assert!(parts.$px.is_high().unwrap());
+                }
+                expander.destroy().done();
+            }
+
             #[test]
             fn can_split_and_get_is_high() {
                 let transactions = [
@@ -0,0 +1,156 @@
+use embedded_hal::i2c::ErrorKind;
+use embedded_hal_mock::eh1::delay::NoopDelay;
+use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTrans};
+use pcf857x::power::{PowerSequencer, SequenceError, Step};
+use pcf857x::{Error, Pcf8574, PinFlag, SlaveAddr};
+
+const DEV_ADDR: u8 = 0b010_0000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Rail {
+    Core,
+    Io,
+}
+
+fn steps() -> [Step<Rail>; 2] {
+    [
+        Step::new(Rail::Core, PinFlag::P0, 1000),
+        Step {
+            power_good: Some(PinFlag::P4),
+            timeout_us: 100,
+            ..Step::new(Rail::Io, PinFlag::P1, 500)
+        },
+    ]
+}
+
+fn new(transactions: &[I2cTrans]) -> PowerSequencer<Pcf8574<I2cMock>, Rail, 2> {
+    let expander = Pcf8574::new(I2cMock::new(transactions), SlaveAddr::default());
+    PowerSequencer::new(expander, steps())
+}
+
+fn read_power_good(last_set: u8, value: u8) -> [I2cTrans; 2] {
+    [
+        I2cTrans::write(DEV_ADDR, vec![last_set | 0b1_0000]),
+        I2cTrans::read(DEV_ADDR, vec![value]),
+    ]
+}
+
+#[test]
+fn new_does_not_communicate() {
+    let sequencer = new(&[]);
+    assert!(!sequencer.is_powered_up());
+    sequencer.destroy().destroy().done();
+}
+
+#[test]
+fn powers_up_in_order() {
+    let mut transactions = vec![
+        I2cTrans::write(DEV_ADDR, vec![0b01]),
+        I2cTrans::write(DEV_ADDR, vec![0b11]),
+    ];
+    transactions.extend(read_power_good(0b11, 0b1_0011));
+    let mut sequencer = new(&transactions);
+    sequencer.power_up(&mut NoopDelay::new()).unwrap();
+    assert!(sequencer.is_powered_up());
+    sequencer.destroy().destroy().done();
+}
+
+#[test]
+fn polls_power_good_until_high() {
+    let mut transactions = vec![
+        I2cTrans::write(DEV_ADDR, vec![0b01]),
+        I2cTrans::write(DEV_ADDR, vec![0b11]),
+    ];
+    transactions.extend(read_power_good(0b11, 0b0011));
+    transactions.extend(read_power_good(0b11, 0b1_0011));
+    let mut sequencer = new(&transactions);
+    sequencer.power_up(&mut NoopDelay::new()).unwrap();
+    sequencer.destroy().destroy().done();
+}
+
+#[test]
+fn power_good_timeout_rolls_back_in_reverse_order() {
+    let mut transactions = vec![
+        I2cTrans::write(DEV_ADDR, vec![0b01]),
+        I2cTrans::write(DEV_ADDR, vec![0b11]),
+    ];
+    transactions.extend(read_power_good(0b11, 0b0011));
+    transactions.extend(read_power_good(0b11, 0b0011));
+    transactions.push(I2cTrans::write(DEV_ADDR, vec![0b01]));
+    transactions.push(I2cTrans::write(DEV_ADDR, vec![0b00]));
+    let mut sequencer = new(&transactions);
+    let error = sequencer.power_up(&mut NoopDelay::new()).unwrap_err();
+    assert_eq!(Rail::Io, *error.step());
+    match error {
+        SequenceError::PowerGoodTimeout(Rail::Io) => (),
+        _ => panic!(),
+    }
+    assert!(!sequencer.is_powered_up());
+    sequencer.destroy().destroy().done();
+}
+
+#[test]
+fn device_error_names_step_and_rolls_back() {
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, vec![0b01]),
+        I2cTrans::write(DEV_ADDR, vec![0b11]).with_error(ErrorKind::Other),
+        I2cTrans::write(DEV_ADDR, vec![0b01]),
+        I2cTrans::write(DEV_ADDR, vec![0b00]),
+    ];
+    let mut sequencer = new(&transactions);
+    match sequencer.power_up(&mut NoopDelay::new()) {
+        Err(SequenceError::Device(Rail::Io, Error::I2C(ErrorKind::Other))) => (),
+        _ => panic!(),
+    }
+    sequencer.destroy().destroy().done();
+}
+
+#[test]
+fn powers_down_in_reverse_order_keeping_other_pins() {
+    let mut transactions = vec![
+        I2cTrans::write(DEV_ADDR, vec![0b1000_0000]),
+        I2cTrans::write(DEV_ADDR, vec![0b1000_0001]),
+        I2cTrans::write(DEV_ADDR, vec![0b1000_0011]),
+    ];
+    transactions.extend(read_power_good(0b1000_0011, 0b1001_0011));
+    transactions.push(I2cTrans::write(DEV_ADDR, vec![0b1000_0001]));
+    transactions.push(I2cTrans::write(DEV_ADDR, vec![0b1000_0000]));
+    let mut expander = Pcf8574::new(I2cMock::new(&transactions), SlaveAddr::default());
+    expander.set(0b1000_0000).unwrap();
+    let mut sequencer = PowerSequencer::new(expander, steps());
+    sequencer.power_up(&mut NoopDelay::new()).unwrap();
+    sequencer.power_down(&mut NoopDelay::new()).unwrap();
+    assert!(!sequencer.is_powered_up());
+    sequencer.destroy().destroy().done();
+}
+
+#[test]
+fn power_up_skips_rails_that_are_on() {
+    let mut transactions = vec![
+        I2cTrans::write(DEV_ADDR, vec![0b01]),
+        I2cTrans::write(DEV_ADDR, vec![0b11]),
+    ];
+    transactions.extend(read_power_good(0b11, 0b1_0011));
+    let mut sequencer = new(&transactions);
+    sequencer.power_up(&mut NoopDelay::new()).unwrap();
+    sequencer.power_up(&mut NoopDelay::new()).unwrap();
+    sequencer.destroy().destroy().done();
+}
+
+#[test]
+fn verified_rails_ignore_power_good_held_low() {
+    let mut transactions = vec![
+        I2cTrans::write(DEV_ADDR, vec![0b01]),
+        I2cTrans::read(DEV_ADDR, vec![0b01]),
+        I2cTrans::write(DEV_ADDR, vec![0b11]),
+        I2cTrans::read(DEV_ADDR, vec![0b0011]),
+    ];
+    transactions.extend(read_power_good(0b11, 0b1_0011));
+    let mut expander = Pcf8574::new(I2cMock::new(&transactions), SlaveAddr::default());
+    expander
+        .set_verify(Some(PinFlag::P0 | PinFlag::P1))
+        .unwrap();
+    let mut sequencer = PowerSequencer::new(expander, steps());
+    sequencer.power_up(&mut NoopDelay::new()).unwrap();
+    sequencer.destroy().destroy().done();
+}
@@ -0,0 +1,140 @@
+use embedded_hal::pwm::SetDutyCycle;
+use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTrans};
+use pcf857x::pwm::{SoftPwm, GAMMA};
+use pcf857x::{Error, Pcf8575, PinFlag, SlaveAddr};
+mod base;
+
+const DEV_ADDR: u8 = 0b010_0000;
+
+fn new(transactions: &[I2cTrans]) -> SoftPwm<Pcf8575<I2cMock>, 2> {
+    let expander = Pcf8575::new(I2cMock::new(transactions), SlaveAddr::default());
+    SoftPwm::new(expander, [PinFlag::P0, PinFlag::P10])
+}
+
+fn writes(values: &[u16]) -> Vec<I2cTrans> {
+    values
+        .iter()
+        .map(|v| I2cTrans::write(DEV_ADDR, vec![*v as u8, (*v >> 8) as u8]))
+        .collect()
+}
+
+fn period(values: &[u16]) -> Vec<u8> {
+    values
+        .iter()
+        .flat_map(|v| vec![*v as u8, (*v >> 8) as u8])
+        .collect()
+}
+
+#[test]
+fn tick_writes_only_changes() {
+    let mut pwm = new(&writes(&[0x0101, 0x0001, 0x0101]));
+    pwm.set_resolution(4).unwrap();
+    pwm.set_duty(0, 255).unwrap();
+    pwm.set_duty(1, 128).unwrap();
+    for _ in 0..5 {
+        pwm.tick().unwrap();
+    }
+    pwm.destroy().destroy().done();
+}
+
+#[test]
+fn all_off_does_not_write() {
+    let mut pwm = new(&[]);
+    for _ in 0..40 {
+        pwm.tick().unwrap();
+    }
+    pwm.destroy().destroy().done();
+}
+
+#[test]
+fn write_period() {
+    let transactions = [I2cTrans::write(
+        DEV_ADDR,
+        period(&[0x0101, 0x0101, 0x0001, 0x0001]),
+    )];
+    let mut pwm = new(&transactions);
+    pwm.set_resolution(4).unwrap();
+    pwm.set_duty(0, 255).unwrap();
+    pwm.set_duty(1, 128).unwrap();
+    pwm.write_period().unwrap();
+    pwm.destroy().destroy().done();
+}
+
+#[test]
+fn write_period_in_chunks() {
+    let mut words = [0; 40];
+    words[..20].copy_from_slice(&[0x0100; 20]);
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, period(&words[..32])),
+        I2cTrans::write(DEV_ADDR, period(&words[32..])),
+    ];
+    let mut pwm = new(&transactions);
+    pwm.set_resolution(40).unwrap();
+    pwm.set_duty(1, 128).unwrap();
+    pwm.write_period().unwrap();
+    pwm.destroy().destroy().done();
+}
+
+#[test]
+fn gamma_correction() {
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, period(&[1, 1, 1, 1, 0, 0, 0, 0])),
+        I2cTrans::write(DEV_ADDR, period(&[1, 1, 0, 0, 0, 0, 0, 0])),
+    ];
+    let mut pwm = new(&transactions);
+    pwm.set_resolution(8).unwrap();
+    pwm.set_duty(0, 128).unwrap();
+    pwm.write_period().unwrap();
+    pwm.set_gamma(Some(&GAMMA));
+    pwm.write_period().unwrap();
+    assert_eq!(128, pwm.duty(0).unwrap());
+    pwm.destroy().destroy().done();
+}
+
+#[test]
+fn keeps_other_pins() {
+    let mut expander = Pcf8575::new(
+        I2cMock::new(&writes(&[0x8000, 0x8001, 0x8000])),
+        SlaveAddr::default(),
+    );
+    expander.set(0x8000).unwrap();
+    let mut pwm = SoftPwm::new(&mut expander, [PinFlag::P0]);
+    pwm.set_resolution(2).unwrap();
+    pwm.set_duty(0, 128).unwrap();
+    pwm.tick().unwrap();
+    pwm.tick().unwrap();
+    expander.destroy().done();
+}
+
+#[test]
+fn invalid_resolution() {
+    let mut pwm = new(&[]);
+    expect_err!(pwm.set_resolution(0), InvalidInputData);
+    expect_err!(pwm.set_resolution(257), InvalidInputData);
+    pwm.set_resolution(256).unwrap();
+    pwm.destroy().destroy().done();
+}
+
+#[test]
+fn invalid_channel() {
+    let mut pwm = new(&[]);
+    expect_err!(pwm.set_duty(2, 0), InvalidInputData);
+    expect_err!(pwm.duty(2), InvalidInputData);
+    assert!(pwm.channel(2).is_err());
+    pwm.destroy().destroy().done();
+}
+
+#[test]
+fn set_duty_cycle() {
+    let mut pwm = new(&[]);
+    {
+        let mut channel = pwm.channel(1).unwrap();
+        assert_eq!(255, channel.max_duty_cycle());
+        channel.set_duty_cycle_percent(50).unwrap();
+        expect_err!(channel.set_duty_cycle(256), InvalidInputData);
+    }
+    assert_eq!(127, pwm.duty(1).unwrap());
+    pwm.channel(0).unwrap().set_duty_cycle_fully_on().unwrap();
+    assert_eq!(255, pwm.duty(0).unwrap());
+    pwm.destroy().destroy().done();
+}
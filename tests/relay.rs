@@ -0,0 +1,224 @@
+use embedded_hal::i2c::ErrorKind;
+use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTrans};
+use pcf857x::relay::{Channel, RelayBank, RelayError};
+use pcf857x::{Error, Pcf8574, PinFlag, SlaveAddr};
+
+const DEV_ADDR: u8 = 0b010_0000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Relay {
+    Forward,
+    Reverse,
+    Pump,
+}
+
+fn channels() -> [Channel<Relay>; 3] {
+    [
+        Channel {
+            interlock_group: Some(0),
+            active_low: true,
+            ..Channel::new(Relay::Forward, PinFlag::P0)
+        },
+        Channel {
+            interlock_group: Some(0),
+            active_low: true,
+            ..Channel::new(Relay::Reverse, PinFlag::P1)
+        },
+        Channel {
+            min_on_time: 100,
+            min_off_time: 500,
+            fail_safe_on: true,
+            ..Channel::new(Relay::Pump, PinFlag::P2)
+        },
+    ]
+}
+
+fn writes(values: &[u8]) -> Vec<I2cTrans> {
+    values
+        .iter()
+        .map(|v| I2cTrans::write(DEV_ADDR, vec![*v]))
+        .collect()
+}
+
+#[test]
+fn writes_fail_safe_pattern_on_creation_and_drop() {
+    let mut expander = Pcf8574::new(I2cMock::new(&writes(&[0b111, 0b111])), SlaveAddr::default());
+    let relays = RelayBank::new(&mut expander, channels(), 0).unwrap();
+    assert!(relays.is_on(Relay::Pump).unwrap());
+    assert!(!relays.is_on(Relay::Forward).unwrap());
+    drop(relays);
+    expander.destroy().done();
+}
+
+#[test]
+fn keeps_other_pins() {
+    let transactions = writes(&[0b1000_0000, 0b1000_0111, 0b1000_0110, 0b1000_0111]);
+    let mut expander = Pcf8574::new(I2cMock::new(&transactions), SlaveAddr::default());
+    expander.set(0b1000_0000).unwrap();
+    let mut relays = RelayBank::new(&mut expander, channels(), 0).unwrap();
+    relays.switch(Relay::Forward, true, 0).unwrap();
+    drop(relays);
+    expander.destroy().done();
+}
+
+#[test]
+fn switching_to_current_state_does_nothing() {
+    let mut expander = Pcf8574::new(I2cMock::new(&writes(&[0b111, 0b111])), SlaveAddr::default());
+    let mut relays = RelayBank::new(&mut expander, channels(), 0).unwrap();
+    relays.switch(Relay::Forward, false, 0).unwrap();
+    relays.switch(Relay::Pump, true, 0).unwrap();
+    drop(relays);
+    expander.destroy().done();
+}
+
+#[test]
+fn interlock_prevents_switching_on() {
+    let transactions = writes(&[0b111, 0b110, 0b111, 0b101, 0b111]);
+    let mut expander = Pcf8574::new(I2cMock::new(&transactions), SlaveAddr::default());
+    let mut relays = RelayBank::new(&mut expander, channels(), 0).unwrap();
+    relays.switch(Relay::Forward, true, 0).unwrap();
+    match relays.switch(Relay::Reverse, true, 0) {
+        Err(RelayError::Interlock) => (),
+        _ => panic!(),
+    }
+    assert!(!relays.is_on(Relay::Reverse).unwrap());
+    relays.switch(Relay::Forward, false, 0).unwrap();
+    relays.switch(Relay::Reverse, true, 0).unwrap();
+    drop(relays);
+    expander.destroy().done();
+}
+
+#[test]
+fn fail_safe_pattern_cannot_violate_interlock() {
+    let mut expander = Pcf8574::new(I2cMock::new(&[]), SlaveAddr::default());
+    let mut channels = channels();
+    channels[0].fail_safe_on = true;
+    channels[1].fail_safe_on = true;
+    match RelayBank::new(&mut expander, channels, 0) {
+        Err(RelayError::Interlock) => (),
+        _ => panic!(),
+    }
+    expander.destroy().done();
+}
+
+#[test]
+fn minimum_times() {
+    let transactions = writes(&[0b111, 0b011, 0b111, 0b111]);
+    let mut expander = Pcf8574::new(I2cMock::new(&transactions), SlaveAddr::default());
+    let mut relays = RelayBank::new(&mut expander, channels(), 0).unwrap();
+    match relays.switch(Relay::Pump, false, 99) {
+        Err(RelayError::MinimumTime) => (),
+        _ => panic!(),
+    }
+    relays.switch(Relay::Pump, false, 100).unwrap();
+    match relays.switch(Relay::Pump, true, 599) {
+        Err(RelayError::MinimumTime) => (),
+        _ => panic!(),
+    }
+    relays.switch(Relay::Pump, true, 600).unwrap();
+    drop(relays);
+    expander.destroy().done();
+}
+
+#[test]
+fn minimum_time_handles_wrap_around() {
+    let transactions = writes(&[0b111, 0b011, 0b111]);
+    let mut expander = Pcf8574::new(I2cMock::new(&transactions), SlaveAddr::default());
+    let mut relays = RelayBank::new(&mut expander, channels(), u32::MAX - 50).unwrap();
+    match relays.switch(Relay::Pump, false, 10) {
+        Err(RelayError::MinimumTime) => (),
+        _ => panic!(),
+    }
+    relays.switch(Relay::Pump, false, 49).unwrap();
+    drop(relays);
+    expander.destroy().done();
+}
+
+#[test]
+fn unknown_channel() {
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Id {
+        A,
+        B,
+    }
+    let mut expander = Pcf8574::new(I2cMock::new(&writes(&[0, 0])), SlaveAddr::default());
+    let mut relays = RelayBank::new(&mut expander, [Channel::new(Id::A, PinFlag::P0)], 0).unwrap();
+    match relays.switch(Id::B, true, 0) {
+        Err(RelayError::UnknownChannel) => (),
+        _ => panic!(),
+    }
+    match relays.is_on(Id::B) {
+        Err(RelayError::UnknownChannel) => (),
+        _ => panic!(),
+    }
+    drop(relays);
+    expander.destroy().done();
+}
+
+#[test]
+fn fail_safe_ignores_minimum_times() {
+    let transactions = writes(&[0b111, 0b110, 0b010, 0b111, 0b111]);
+    let mut expander = Pcf8574::new(I2cMock::new(&transactions), SlaveAddr::default());
+    let mut relays = RelayBank::new(&mut expander, channels(), 0).unwrap();
+    relays.switch(Relay::Forward, true, 0).unwrap();
+    relays.switch(Relay::Pump, false, 100).unwrap();
+    relays.fail_safe(101).unwrap();
+    assert!(!relays.is_on(Relay::Forward).unwrap());
+    assert!(relays.is_on(Relay::Pump).unwrap());
+    drop(relays);
+    expander.destroy().done();
+}
+
+#[test]
+fn writes_fail_safe_pattern_after_bus_error() {
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, vec![0b111]),
+        I2cTrans::write(DEV_ADDR, vec![0b110]).with_error(ErrorKind::Other),
+        I2cTrans::write(DEV_ADDR, vec![0b111]),
+        I2cTrans::write(DEV_ADDR, vec![0b111]),
+    ];
+    let mut expander = Pcf8574::new(I2cMock::new(&transactions), SlaveAddr::default());
+    let mut relays = RelayBank::new(&mut expander, channels(), 0).unwrap();
+    match relays.switch(Relay::Forward, true, 0) {
+        Err(RelayError::Device(Error::I2C(ErrorKind::Other))) => (),
+        _ => panic!(),
+    }
+    assert!(!relays.is_on(Relay::Forward).unwrap());
+    drop(relays);
+    expander.destroy().done();
+}
+
+#[test]
+fn failed_creation_writes_once() {
+    let transactions = [I2cTrans::write(DEV_ADDR, vec![0b111]).with_error(ErrorKind::Other)];
+    let mut expander = Pcf8574::new(I2cMock::new(&transactions), SlaveAddr::default());
+    match RelayBank::new(&mut expander, channels(), 0) {
+        Err(RelayError::Device(Error::I2C(ErrorKind::Other))) => (),
+        _ => panic!(),
+    }
+    expander.destroy().done();
+}
+
+#[test]
+fn fail_safe_after_bus_error_restarts_minimum_times() {
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, vec![0b111]),
+        I2cTrans::write(DEV_ADDR, vec![0b110]),
+        I2cTrans::write(DEV_ADDR, vec![0b010]),
+        I2cTrans::write(DEV_ADDR, vec![0b011]).with_error(ErrorKind::Other),
+        I2cTrans::write(DEV_ADDR, vec![0b111]),
+        I2cTrans::write(DEV_ADDR, vec![0b111]),
+    ];
+    let mut expander = Pcf8574::new(I2cMock::new(&transactions), SlaveAddr::default());
+    let mut relays = RelayBank::new(&mut expander, channels(), 0).unwrap();
+    relays.switch(Relay::Forward, true, 0).unwrap();
+    relays.switch(Relay::Pump, false, 100).unwrap();
+    assert!(relays.switch(Relay::Forward, false, 200).is_err());
+    assert!(relays.is_on(Relay::Pump).unwrap());
+    match relays.switch(Relay::Pump, false, 250) {
+        Err(RelayError::MinimumTime) => (),
+        _ => panic!(),
+    }
+    drop(relays);
+    expander.destroy().done();
+}
@@ -0,0 +1,119 @@
+use embedded_hal::i2c::ErrorKind;
+use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTrans};
+use pcf857x::self_test::loopback;
+use pcf857x::{Error, Pcf8574, PinFlag, SlaveAddr};
+mod base;
+
+const DEV_ADDR: u8 = 0b010_0000;
+const PAIRS: [(PinFlag, PinFlag); 2] = [(PinFlag::P0, PinFlag::P1), (PinFlag::P2, PinFlag::P3)];
+
+// P7 is an output set high before the test, P4-P6 are low
+fn transactions(reads: [u8; 5]) -> Vec<I2cTrans> {
+    let mut transactions = vec![
+        I2cTrans::write(DEV_ADDR, vec![0x80]),
+        I2cTrans::write(DEV_ADDR, vec![0x8F]),
+        I2cTrans::write(DEV_ADDR, vec![0x8F]),
+        I2cTrans::read(DEV_ADDR, vec![reads[0]]),
+    ];
+    for (i, read) in reads[1..].iter().enumerate() {
+        let word = 0x8F & !(1 << i);
+        transactions.push(I2cTrans::write(DEV_ADDR, vec![word]));
+        transactions.push(I2cTrans::write(DEV_ADDR, vec![word]));
+        transactions.push(I2cTrans::read(DEV_ADDR, vec![*read]));
+    }
+    transactions.push(I2cTrans::write(DEV_ADDR, vec![0x80]));
+    transactions
+}
+
+fn new(transactions: &[I2cTrans]) -> Pcf8574<I2cMock> {
+    let mut expander = Pcf8574::new(I2cMock::new(transactions), SlaveAddr::default());
+    expander.set(0x80).unwrap();
+    expander
+}
+
+#[test]
+fn passes_with_good_fixture() {
+    let mut expander = new(&transactions([0x8F, 0x8C, 0x8C, 0x83, 0x83]));
+    let report = loopback(&mut expander, &PAIRS).unwrap();
+    assert!(report.passed());
+    expander.destroy().done();
+}
+
+#[test]
+fn reports_open_connection() {
+    let mut expander = new(&transactions([0x8F, 0x8C, 0x8C, 0x8B, 0x87]));
+    let report = loopback(&mut expander, &PAIRS).unwrap();
+    assert!(!report.passed());
+    assert_eq!(0b1100, report.open);
+    assert_eq!(0, report.stuck_low);
+    expander.destroy().done();
+}
+
+#[test]
+fn reports_short_between_pins() {
+    let mut expander = new(&transactions([0x8F, 0x8C, 0x88, 0x83, 0x83]));
+    let report = loopback(&mut expander, &PAIRS).unwrap();
+    assert!(!report.passed());
+    assert_eq!(0, report.open);
+    assert_eq!(0, report.shorts(PinFlag::P0));
+    assert_eq!(0b0100, report.shorts(PinFlag::P1));
+    assert_eq!(0b0100, report.shorts(PinFlag::P0 | PinFlag::P1));
+    expander.destroy().done();
+}
+
+#[test]
+fn reports_stuck_low_pin_without_shorts() {
+    let mut expander = new(&transactions([0x8E, 0x8C, 0x8C, 0x82, 0x82]));
+    let report = loopback(&mut expander, &PAIRS).unwrap();
+    assert!(!report.passed());
+    assert_eq!(0b0001, report.stuck_low);
+    assert_eq!(0, report.open);
+    assert_eq!(0, report.shorts(PinFlag::P2 | PinFlag::P3));
+    expander.destroy().done();
+}
+
+#[test]
+fn rejects_invalid_pairs() {
+    let mut expander = new(&[I2cTrans::write(DEV_ADDR, vec![0x80])]);
+    expect_err!(
+        loopback(&mut expander, &[(PinFlag::P0, PinFlag::P0)]),
+        InvalidInputData
+    );
+    expect_err!(
+        loopback(&mut expander, &[(PinFlag::P0 | PinFlag::P1, PinFlag::P2)]),
+        InvalidInputData
+    );
+    expander.destroy().done();
+}
+
+#[test]
+fn rejects_verified_pins() {
+    let mut expander = new(&[I2cTrans::write(DEV_ADDR, vec![0x80])]);
+    expander.set_verify(Some(PinFlag::P1)).unwrap();
+    expect_err!(loopback(&mut expander, &PAIRS), InvalidInputData);
+    expander.destroy().done();
+}
+
+#[test]
+fn tests_inverted_pins_with_physical_levels() {
+    let mut expander = new(&transactions([0x8F, 0x8C, 0x8C, 0x83, 0x83]));
+    expander.set_inversion_mask(0x0F);
+    let report = loopback(&mut expander, &PAIRS).unwrap();
+    assert!(report.passed());
+    expander.destroy().done();
+}
+
+#[test]
+fn restores_status_after_device_error() {
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, vec![0x80]),
+        I2cTrans::write(DEV_ADDR, vec![0x8F]).with_error(ErrorKind::Other),
+        I2cTrans::write(DEV_ADDR, vec![0x80]),
+    ];
+    let mut expander = new(&transactions);
+    match loopback(&mut expander, &PAIRS) {
+        Err(Error::I2C(ErrorKind::Other)) => (),
+        _ => panic!(),
+    }
+    expander.destroy().done();
+}
@@ -0,0 +1,108 @@
+use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTrans};
+use pcf857x::seven_segment::{font, Pins, Polarity, SevenSegment, SEG_DP};
+use pcf857x::{Error, Pcf8575, PinFlag, SlaveAddr};
+mod base;
+
+const DEV_ADDR: u8 = 0b010_0000;
+
+fn new(transactions: &[I2cTrans]) -> SevenSegment<Pcf8575<I2cMock>, 4> {
+    let pins = Pins {
+        segments: [
+            PinFlag::P0,
+            PinFlag::P1,
+            PinFlag::P2,
+            PinFlag::P3,
+            PinFlag::P4,
+            PinFlag::P5,
+            PinFlag::P6,
+            PinFlag::P7,
+        ],
+        digits: [PinFlag::P10, PinFlag::P11, PinFlag::P12, PinFlag::P13],
+        segment_polarity: Polarity::ActiveLow,
+        digit_polarity: Polarity::ActiveHigh,
+    };
+    let expander = Pcf8575::new(I2cMock::new(transactions), SlaveAddr::default());
+    SevenSegment::new(expander, pins)
+}
+
+#[test]
+fn font_contains_hex_digits() {
+    assert_eq!(Some(0x3F), font('0'));
+    assert_eq!(Some(0x7F), font('8'));
+    assert_eq!(Some(0x77), font('a'));
+    assert_eq!(Some(0x71), font('F'));
+    assert_eq!(None, font('W'));
+}
+
+#[test]
+fn can_write_text_with_decimal_point() {
+    let mut display = new(&[]);
+    display.write_str("1.2-").unwrap();
+    assert_eq!(&[0x06 | SEG_DP, 0x5B, 0x40, 0], display.segments());
+    display.destroy().destroy().done();
+}
+
+#[test]
+fn invalid_text_returns_error() {
+    let mut display = new(&[]);
+    display.write_str("12").unwrap();
+    expect_err!(display.write_str("12345"), InvalidInputData);
+    expect_err!(display.write_str("W"), InvalidInputData);
+    assert_eq!(&[0x06, 0x5B, 0, 0], display.segments());
+    display.destroy().destroy().done();
+}
+
+#[test]
+fn tick_shows_one_digit_per_set() {
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, vec![!0x06, 0x01]),
+        I2cTrans::write(DEV_ADDR, vec![!0x5B, 0x02]),
+        I2cTrans::write(DEV_ADDR, vec![0xFF, 0x04]),
+        I2cTrans::write(DEV_ADDR, vec![0xFF, 0x08]),
+        I2cTrans::write(DEV_ADDR, vec![!0x06, 0x01]),
+    ];
+    let mut display = new(&transactions);
+    display.write_str("12").unwrap();
+    for _ in 0..5 {
+        display.tick().unwrap();
+    }
+    display.destroy().destroy().done();
+}
+
+#[test]
+fn set_segments_of_missing_digit_returns_error() {
+    let mut display = new(&[]);
+    display.set_segments(3, 0xFF).unwrap();
+    expect_err!(display.set_segments(4, 0xFF), InvalidInputData);
+    display.destroy().destroy().done();
+}
+
+#[test]
+fn blank_keeps_other_pins() {
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, vec![0x00, 0x80]),
+        I2cTrans::write(DEV_ADDR, vec![0xFF, 0x80]),
+    ];
+    let mut display = new(&transactions);
+    display.set_segments(0, 0xFF).unwrap();
+    let mut expander = display.destroy();
+    expander.set(0x8000).unwrap();
+    let pins = Pins {
+        segments: [
+            PinFlag::P0,
+            PinFlag::P1,
+            PinFlag::P2,
+            PinFlag::P3,
+            PinFlag::P4,
+            PinFlag::P5,
+            PinFlag::P6,
+            PinFlag::P7,
+        ],
+        digits: [PinFlag::P10],
+        segment_polarity: Polarity::ActiveLow,
+        digit_polarity: Polarity::ActiveHigh,
+    };
+    let mut display = SevenSegment::new(&mut expander, pins);
+    display.blank().unwrap();
+    expander.destroy().done();
+}
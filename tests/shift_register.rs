@@ -0,0 +1,121 @@
+use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTrans};
+use pcf857x::shift_register::{Hc165, Hc165Pins, Hc595, Hc595Pins};
+use pcf857x::{Error, Pcf8574, PinFlag, SlaveAddr};
+mod base;
+
+const DEV_ADDR: u8 = 0b010_0000;
+
+const HC595_PINS: Hc595Pins = Hc595Pins {
+    data: PinFlag::P0,
+    clock: PinFlag::P1,
+    latch: PinFlag::P2,
+};
+
+const HC165_PINS: Hc165Pins = Hc165Pins {
+    data: PinFlag::P4,
+    clock: PinFlag::P5,
+    load: PinFlag::P6,
+};
+
+/// Port words to shift a chain of 74HC595, last register first
+fn shift_out(outputs: &[u8], other: u8) -> Vec<u8> {
+    let mut words = Vec::new();
+    for byte in outputs.iter().rev() {
+        for bit in (0..8).rev() {
+            let data = (byte >> bit) & 1;
+            words.extend([other | data, other | data | 0b10]);
+        }
+    }
+    words.extend([other | 0b100, other]);
+    words
+}
+
+fn hc595<const N: usize>(transactions: &[I2cTrans]) -> Hc595<Pcf8574<I2cMock>, N> {
+    let expander = Pcf8574::new(I2cMock::new(transactions), SlaveAddr::default());
+    Hc595::new(expander, HC595_PINS)
+}
+
+#[test]
+fn hc595_write() {
+    let data = shift_out(&[0x01, 0x80], 0);
+    let mut chain = hc595::<2>(&[I2cTrans::write(DEV_ADDR, data)]);
+    chain.write([0x01, 0x80]).unwrap();
+    assert_eq!(&[0x01, 0x80], chain.outputs());
+    chain.destroy().destroy().done();
+}
+
+#[test]
+fn hc595_long_chain_is_written_in_chunks() {
+    let data = shift_out(&[1, 2, 3, 4], 0);
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, data[..64].to_vec()),
+        I2cTrans::write(DEV_ADDR, data[64..].to_vec()),
+    ];
+    let mut chain = hc595::<4>(&transactions);
+    chain.write([1, 2, 3, 4]).unwrap();
+    chain.destroy().destroy().done();
+}
+
+#[test]
+fn hc595_set_output() {
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, shift_out(&[0, 0x40], 0)),
+        I2cTrans::write(DEV_ADDR, shift_out(&[0x01, 0x40], 0)),
+    ];
+    let mut chain = hc595::<2>(&transactions);
+    chain.set_output(14, true).unwrap();
+    chain.set_output(0, true).unwrap();
+    expect_err!(chain.set_output(16, true), InvalidInputData);
+    assert_eq!(&[0x01, 0x40], chain.outputs());
+    chain.destroy().destroy().done();
+}
+
+#[test]
+fn hc595_keeps_other_pins() {
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, vec![0x80]),
+        I2cTrans::write(DEV_ADDR, shift_out(&[0x55], 0x80)),
+    ];
+    let mut expander = Pcf8574::new(I2cMock::new(&transactions), SlaveAddr::default());
+    expander.set(0x80).unwrap();
+    let mut chain: Hc595<_, 1> = Hc595::new(&mut expander, HC595_PINS);
+    chain.write([0x55]).unwrap();
+    expander.destroy().done();
+}
+
+/// Expander transactions to load and shift a chain of 74HC165 with the given inputs
+fn shift_in(inputs: &[u8]) -> Vec<I2cTrans> {
+    let mut transactions = vec![I2cTrans::write(DEV_ADDR, vec![0x10, 0x50])];
+    for (i, byte) in inputs.iter().enumerate() {
+        for bit in (0..8).rev() {
+            if i > 0 || bit < 7 {
+                transactions.push(I2cTrans::write(DEV_ADDR, vec![0x70, 0x50]));
+            }
+            let data = ((byte >> bit) & 1) << 4;
+            transactions.push(I2cTrans::write(DEV_ADDR, vec![0x50]));
+            transactions.push(I2cTrans::read(DEV_ADDR, vec![0x40 | data]));
+        }
+    }
+    transactions
+}
+
+#[test]
+fn hc165_read() {
+    let inputs = [0xA5, 0x3C];
+    let transactions = shift_in(&inputs);
+    let expander = Pcf8574::new(I2cMock::new(&transactions), SlaveAddr::default());
+    let mut chain: Hc165<_, 2> = Hc165::new(expander, HC165_PINS);
+    assert_eq!(inputs, chain.read().unwrap());
+    chain.destroy().destroy().done();
+}
+
+#[test]
+fn hc165_reads_inverted_data_pin_physically() {
+    let inputs = [0x96];
+    let transactions = shift_in(&inputs);
+    let mut expander = Pcf8574::new(I2cMock::new(&transactions), SlaveAddr::default());
+    expander.set_inversion_mask(0x10);
+    let mut chain: Hc165<_, 1> = Hc165::new(expander, HC165_PINS);
+    assert_eq!(inputs, chain.read().unwrap());
+    chain.destroy().destroy().done();
+}
@@ -0,0 +1,230 @@
+use embedded_hal::i2c::ErrorKind;
+use embedded_hal::spi::{Operation, SpiBus, SpiDevice};
+use embedded_hal_mock::eh1::delay::NoopDelay;
+use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTrans};
+use pcf857x::spi::{BitBangBus, BitBangDevice, Mode, Pins, MODE_0, MODE_1, MODE_2, MODE_3};
+use pcf857x::{Error, Pcf8574, Pcf8575, PinFlag, SlaveAddr};
+
+const DEV_ADDR: u8 = 0b010_0000;
+const SCK: u8 = 0b0001;
+const MOSI: u8 = 0b0010;
+const MISO: u8 = 0b0100;
+const CS: u8 = 0b1000;
+
+const PINS: Pins = Pins {
+    sck: PinFlag::P0,
+    mosi: PinFlag::P1,
+    miso: PinFlag::P2,
+};
+
+fn new(transactions: &[I2cTrans], mode: Mode) -> BitBangBus<Pcf8574<I2cMock>> {
+    let expander = Pcf8574::new(I2cMock::new(transactions), SlaveAddr::default());
+    BitBangBus::new(expander, PINS, mode)
+}
+
+/// Port words to shift a byte out, with the clock levels of the two words of each bit
+fn shift_out(byte: u8, first: u8, second: u8) -> Vec<u8> {
+    (0..8)
+        .rev()
+        .flat_map(|bit| {
+            let mosi = if byte & (1 << bit) != 0 { MOSI } else { 0 };
+            vec![MISO | first | mosi, MISO | second | mosi]
+        })
+        .collect()
+}
+
+#[test]
+fn write_mode_0() {
+    let mut data = shift_out(0xA5, 0, SCK);
+    data.push(MISO | MOSI);
+    let mut spi = new(&[I2cTrans::write(DEV_ADDR, data)], MODE_0);
+    spi.write(&[0xA5]).unwrap();
+    spi.destroy().destroy().done();
+}
+
+#[test]
+fn write_mode_3_sets_clock_idle_first() {
+    let mut data = vec![MISO | SCK];
+    data.extend(shift_out(0x3C, 0, SCK));
+    let mut spi = new(&[I2cTrans::write(DEV_ADDR, data)], MODE_3);
+    spi.write(&[0x3C]).unwrap();
+    spi.destroy().destroy().done();
+}
+
+#[test]
+fn write_is_batched() {
+    let mut data = Vec::new();
+    for byte in [1, 2, 3, 4, 5] {
+        data.extend(shift_out(byte, 0, SCK));
+    }
+    data.push(MISO | MOSI);
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, data[..64].to_vec()),
+        I2cTrans::write(DEV_ADDR, data[64..].to_vec()),
+    ];
+    let mut spi = new(&transactions, MODE_0);
+    spi.write(&[1, 2, 3, 4, 5]).unwrap();
+    spi.destroy().destroy().done();
+}
+
+fn read_bit(words: [u8; 2], miso: bool) -> Vec<I2cTrans> {
+    let sample = if miso { MISO } else { 0 };
+    vec![
+        I2cTrans::write(DEV_ADDR, words.to_vec()),
+        I2cTrans::write(DEV_ADDR, vec![words[1] | MISO]),
+        I2cTrans::read(DEV_ADDR, vec![words[1] & !MISO | sample]),
+    ]
+}
+
+fn read_mode_0_transactions(byte: u8) -> Vec<I2cTrans> {
+    let mut transactions = Vec::new();
+    for bit in (0..8).rev() {
+        transactions.extend(read_bit([MISO, MISO | SCK], byte & (1 << bit) != 0));
+    }
+    transactions.push(I2cTrans::write(DEV_ADDR, vec![MISO]));
+    transactions
+}
+
+#[test]
+fn read_mode_0() {
+    let mut spi = new(&read_mode_0_transactions(0x96), MODE_0);
+    let mut data = [0];
+    spi.read(&mut data).unwrap();
+    assert_eq!(0x96, data[0]);
+    spi.destroy().destroy().done();
+}
+
+#[test]
+fn miso_uses_physical_level_when_inverted() {
+    let transactions = read_mode_0_transactions(0x96);
+    let mut expander = Pcf8574::new(I2cMock::new(&transactions), SlaveAddr::default());
+    expander.set_inversion_mask(MISO);
+    let mut spi = BitBangBus::new(expander, PINS, MODE_0);
+    let mut data = [0];
+    spi.read(&mut data).unwrap();
+    assert_eq!(0x96, data[0]);
+    spi.destroy().destroy().done();
+}
+
+#[test]
+fn transfer_in_place_mode_3() {
+    let mut transactions = Vec::new();
+    for bit in (0..8).rev() {
+        let mosi = if 0xF0 & (1 << bit) != 0 { MOSI } else { 0 };
+        let words = [MISO | mosi, MISO | SCK | mosi];
+        transactions.extend(read_bit(words, 0x0F & (1 << bit) != 0));
+    }
+    // the clock is set idle in the same write as the first bit
+    transactions[0] = I2cTrans::write(DEV_ADDR, vec![MISO | SCK, MISO | MOSI, MISO | SCK | MOSI]);
+    let mut spi = new(&transactions, MODE_3);
+    let mut data = [0xF0];
+    spi.transfer_in_place(&mut data).unwrap();
+    assert_eq!(0x0F, data[0]);
+    spi.destroy().destroy().done();
+}
+
+#[test]
+fn transfer_mode_1_shifts_on_rising_and_samples_on_falling_edge() {
+    let mut transactions = Vec::new();
+    for bit in (0..8).rev() {
+        let mosi = if 0xF0 & (1 << bit) != 0 { MOSI } else { 0 };
+        let words = [MISO | SCK | mosi, MISO | mosi];
+        transactions.extend(read_bit(words, 0x0F & (1 << bit) != 0));
+    }
+    let mut spi = new(&transactions, MODE_1);
+    let mut data = [0];
+    spi.transfer(&mut data, &[0xF0]).unwrap();
+    assert_eq!(0x0F, data[0]);
+    spi.destroy().destroy().done();
+}
+
+#[test]
+fn transfer_mode_2_samples_on_falling_and_shifts_on_rising_edge() {
+    let mut transactions = Vec::new();
+    for bit in (0..8).rev() {
+        let mosi = if 0x0F & (1 << bit) != 0 { MOSI } else { 0 };
+        let words = [MISO | SCK | mosi, MISO | mosi];
+        transactions.extend(read_bit(words, 0xF0 & (1 << bit) != 0));
+    }
+    // the clock is set idle in the same write as the first bit
+    transactions[0] = I2cTrans::write(DEV_ADDR, vec![MISO | SCK, MISO | SCK, MISO]);
+    // and returned to idle at the end
+    transactions.push(I2cTrans::write(DEV_ADDR, vec![MISO | SCK | MOSI]));
+    let mut spi = new(&transactions, MODE_2);
+    let mut data = [0];
+    spi.transfer(&mut data, &[0x0F]).unwrap();
+    assert_eq!(0xF0, data[0]);
+    spi.destroy().destroy().done();
+}
+
+#[test]
+fn device_selects_chip() {
+    let mut data = vec![MISO | CS, MISO];
+    data.extend(shift_out(0xC3, 0, SCK));
+    data.extend([MISO | MOSI, MISO | MOSI | CS]);
+    let bus = new(&[I2cTrans::write(DEV_ADDR, data)], MODE_0);
+    let mut device = BitBangDevice::new(bus, PinFlag::P3, NoopDelay::new());
+    device.write(&[0xC3]).unwrap();
+    let (bus, _) = device.destroy();
+    bus.destroy().destroy().done();
+}
+
+#[test]
+fn device_deselects_chip_after_error() {
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, vec![MISO | CS, MISO, MISO, MISO | SCK])
+            .with_error(ErrorKind::Other),
+        I2cTrans::write(DEV_ADDR, vec![MISO | SCK | CS]),
+    ];
+    let bus = new(&transactions, MODE_0);
+    let mut device = BitBangDevice::new(bus, PinFlag::P3, NoopDelay::new());
+    let mut data = [0];
+    match device.read(&mut data) {
+        Err(Error::I2C(ErrorKind::Other)) => (),
+        _ => panic!(),
+    }
+    let (bus, _) = device.destroy();
+    bus.destroy().destroy().done();
+}
+
+#[test]
+fn device_delay_flushes() {
+    let mut first = vec![MISO | CS, MISO];
+    first.extend(shift_out(0x01, 0, SCK));
+    first.push(MISO | MOSI);
+    let mut second = shift_out(0x80, 0, SCK);
+    second.extend([MISO, MISO | CS]);
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, first),
+        I2cTrans::write(DEV_ADDR, second),
+    ];
+    let bus = new(&transactions, MODE_0);
+    let mut device = BitBangDevice::new(bus, PinFlag::P3, NoopDelay::new());
+    device
+        .transaction(&mut [
+            Operation::Write(&[0x01]),
+            Operation::DelayNs(1000),
+            Operation::Write(&[0x80]),
+        ])
+        .unwrap();
+    let (bus, _) = device.destroy();
+    bus.destroy().destroy().done();
+}
+
+#[test]
+fn keeps_other_pins_on_pcf8575() {
+    let mut data = Vec::new();
+    for word in shift_out(0xFF, 0, SCK) {
+        data.extend([word, 0x80]);
+    }
+    data.extend([MISO | MOSI, 0x80]);
+    let transactions = [
+        I2cTrans::write(DEV_ADDR, vec![0, 0x80]),
+        I2cTrans::write(DEV_ADDR, data),
+    ];
+    let mut expander = Pcf8575::new(I2cMock::new(&transactions), SlaveAddr::default());
+    expander.set(0x8000).unwrap();
+    let mut spi = BitBangBus::new(&mut expander, PINS, MODE_0);
+    spi.write(&[0xFF]).unwrap();
+    expander.destroy().done();
+}
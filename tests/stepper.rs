@@ -0,0 +1,111 @@
+use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTrans};
+use pcf857x::stepper::{Direction, StepMode, Steppers};
+use pcf857x::{Error, Pcf8574, PinFlag, SlaveAddr};
+mod base;
+
+const DEV_ADDR: u8 = 0b010_0000;
+
+fn new(transactions: &[I2cTrans]) -> Steppers<Pcf8574<I2cMock>, 2> {
+    let expander = Pcf8574::new(I2cMock::new(transactions), SlaveAddr::default());
+    Steppers::new(
+        expander,
+        [
+            [PinFlag::P0, PinFlag::P1, PinFlag::P2, PinFlag::P3],
+            [PinFlag::P4, PinFlag::P5, PinFlag::P6, PinFlag::P7],
+        ],
+    )
+}
+
+fn writes(values: &[u8]) -> Vec<I2cTrans> {
+    values
+        .iter()
+        .map(|v| I2cTrans::write(DEV_ADDR, vec![*v]))
+        .collect()
+}
+
+#[test]
+fn full_step_sequence() {
+    let mut motors = new(&writes(&[0b0110, 0b1100, 0b1001, 0b0011, 0b0110]));
+    motors.move_to(0, 5).unwrap();
+    for now in 0..6 {
+        motors.tick(now).unwrap();
+    }
+    assert_eq!(5, motors.position(0).unwrap());
+    assert!(!motors.is_moving(0).unwrap());
+    motors.destroy().destroy().done();
+}
+
+#[test]
+fn wave_sequence_backward() {
+    let mut motors = new(&writes(&[0b0001, 0b1000, 0b0100]));
+    motors.set_mode(0, StepMode::Wave).unwrap();
+    motors.move_by(0, -3).unwrap();
+    for now in 0..3 {
+        motors.tick(now).unwrap();
+    }
+    assert_eq!(-3, motors.position(0).unwrap());
+    motors.destroy().destroy().done();
+}
+
+#[test]
+fn half_step_sequence() {
+    let mut motors = new(&writes(&[0b0010, 0b0110, 0b0100]));
+    motors.set_mode(0, StepMode::Half).unwrap();
+    motors.move_to(0, 3).unwrap();
+    for now in 0..3 {
+        motors.tick(now).unwrap();
+    }
+    motors.destroy().destroy().done();
+}
+
+#[test]
+fn motors_are_updated_in_one_write_per_step() {
+    let mut motors = new(&writes(&[0b1001_0110]));
+    motors.move_to(0, 2).unwrap();
+    motors.move_to(1, -1).unwrap();
+    motors.set_step_interval(0, 10).unwrap();
+    assert!(motors.tick(10).unwrap());
+    assert!(!motors.tick(15).unwrap());
+    motors.destroy().destroy().done();
+}
+
+#[test]
+fn respects_step_interval() {
+    let mut motors = new(&writes(&[0b0110, 0b1100]));
+    motors.set_step_interval(0, 100).unwrap();
+    motors.move_to(0, 2).unwrap();
+    assert!(motors.tick(100).unwrap());
+    assert!(!motors.tick(199).unwrap());
+    assert!(motors.tick(200).unwrap());
+    motors.destroy().destroy().done();
+}
+
+#[test]
+fn can_step_and_release() {
+    let mut motors = new(&writes(&[0b0110_0000, 0]));
+    motors.step(1, Direction::Forward).unwrap();
+    assert_eq!(1, motors.position(1).unwrap());
+    motors.release(1).unwrap();
+    motors.destroy().destroy().done();
+}
+
+#[test]
+fn conserves_other_pins() {
+    let transactions = writes(&[0b1111_0000, 0b1111_0110]);
+    let mut expander = Pcf8574::new(I2cMock::new(&transactions), SlaveAddr::default());
+    expander.set(0b1111_0000).unwrap();
+    let mut motors = Steppers::new(
+        &mut expander,
+        [[PinFlag::P0, PinFlag::P1, PinFlag::P2, PinFlag::P3]],
+    );
+    motors.step(0, Direction::Forward).unwrap();
+    expander.destroy().done();
+}
+
+#[test]
+fn invalid_motor_returns_error() {
+    let mut motors = new(&[]);
+    expect_err!(motors.move_to(2, 1), InvalidInputData);
+    expect_err!(motors.position(2), InvalidInputData);
+    motors.destroy().destroy().done();
+}